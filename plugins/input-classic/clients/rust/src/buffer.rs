@@ -0,0 +1,127 @@
+//! Input buffering: a press made slightly too early (say, jumping a few frames before landing)
+//! still counts once the game is ready for it.
+
+use crate::state::{Button, ControllerState, Player};
+use crate::timing::{Clock, Span, Tick};
+
+/// Remembers each player's recent button presses for a window of time. Call
+/// [`InputBuffer::update`] once per frame, then [`InputBuffer::consume_buffered`] when the game
+/// can act on a press.
+pub struct InputBuffer {
+    window: Span,
+    /// When each button was last pressed, until it expires or is consumed.
+    presses: [[Option<Tick>; Button::COUNT]; 2],
+    previous: ControllerState,
+    clock: Clock,
+    now: Tick,
+}
+
+impl InputBuffer {
+    /// Keeps presses for `window`, e.g. `Span::Frames(6)`.
+    pub fn new(window: Span) -> Self {
+        Self {
+            window,
+            presses: Default::default(),
+            previous: ControllerState::default(),
+            clock: Clock::default(),
+            now: Tick::default(),
+        }
+    }
+
+    pub fn window(&self) -> Span {
+        self.window
+    }
+
+    pub fn set_window(&mut self, window: Span) {
+        self.window = window;
+    }
+
+    /// Feeds one frame of input, buffering new presses and dropping expired ones.
+    pub fn update(&mut self, state: &ControllerState, now_ms: f64) {
+        self.now = self.clock.tick(now_ms);
+        let previous = std::mem::replace(&mut self.previous, *state);
+
+        for player in Player::ALL {
+            for button in Button::ALL {
+                let press = &mut self.presses[player.index()][button.index()];
+
+                if state.is_pressed(player, button) && !previous.is_pressed(player, button) {
+                    *press = Some(self.now);
+                } else if press.is_some_and(|since| !self.window.contains(since, self.now)) {
+                    *press = None;
+                }
+            }
+        }
+    }
+
+    /// Whether the button was pressed within the window and not consumed yet.
+    pub fn is_buffered(&self, player: Player, button: Button) -> bool {
+        self.presses[player.index()][button.index()].is_some()
+    }
+
+    /// Takes a buffered press of the button, so it only triggers one action. Returns `false` if
+    /// there was none.
+    pub fn consume_buffered(&mut self, player: Player, button: Button) -> bool {
+        self.presses[player.index()][button.index()]
+            .take()
+            .is_some()
+    }
+
+    /// Forgets all of the player's buffered presses, for example on respawn.
+    pub fn clear(&mut self, player: Player) {
+        self.presses[player.index()] = Default::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::test_states::holding;
+
+    #[test]
+    fn a_press_stays_buffered_for_the_window() {
+        let mut buffer = InputBuffer::new(Span::Frames(3));
+        buffer.update(&holding(&[Button::A]), 0.0);
+        for _ in 0..3 {
+            buffer.update(&holding(&[]), 0.0);
+            assert!(buffer.is_buffered(Player::One, Button::A));
+        }
+
+        buffer.update(&holding(&[]), 0.0);
+        assert!(!buffer.is_buffered(Player::One, Button::A));
+    }
+
+    #[test]
+    fn a_press_is_consumed_once() {
+        let mut buffer = InputBuffer::new(Span::Millis(100.0));
+        buffer.update(&holding(&[Button::A]), 0.0);
+        buffer.update(&holding(&[Button::A]), 50.0);
+
+        assert!(buffer.consume_buffered(Player::One, Button::A));
+        assert!(!buffer.consume_buffered(Player::One, Button::A));
+    }
+
+    #[test]
+    fn holding_a_button_does_not_buffer_it_again() {
+        let mut buffer = InputBuffer::new(Span::Frames(1));
+        buffer.update(&holding(&[Button::A]), 0.0);
+        for _ in 0..3 {
+            buffer.update(&holding(&[Button::A]), 0.0);
+        }
+
+        assert!(!buffer.is_buffered(Player::One, Button::A));
+        assert!(!buffer.is_buffered(Player::Two, Button::A));
+    }
+
+    #[test]
+    fn clear_forgets_only_that_player() {
+        let mut buffer = InputBuffer::new(Span::Frames(6));
+        let mut state = holding(&[Button::A]);
+        state.set_pressed(Player::Two, Button::B, true);
+        buffer.update(&state, 0.0);
+
+        buffer.clear(Player::One);
+        assert!(!buffer.is_buffered(Player::One, Button::A));
+        assert!(buffer.is_buffered(Player::Two, Button::B));
+    }
+}
@@ -0,0 +1,324 @@
+//! Recognizes motion inputs and button sequences, such as quarter-circle-forward + A, double
+//! taps, or cheat codes.
+//!
+//! Combos are written as if the player faces right. Each player's facing can be changed with
+//! [`ComboRecognizer::set_facing`], and their inputs are mirrored before matching, so "forward"
+//! always means towards the opponent.
+//!
+//! Steps must happen in order and the whole combo must fit in its window, but other inputs may
+//! happen between steps.
+
+use std::collections::VecDeque;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::state::{Button, ControllerState, Direction, Player, PlayerState};
+use crate::timing::{Clock, Span, Tick};
+
+/// Inputs older than this are dropped even if a combo window would still contain them.
+const MAX_HISTORY: usize = 64;
+
+/// One input in a combo.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Step {
+    /// The d-pad moves into this direction.
+    Direction(Direction),
+    Press(Button),
+    Release(Button),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Combo {
+    pub steps: Vec<Step>,
+    /// Maximum time from the first step to the last.
+    pub window: Span,
+}
+
+impl Combo {
+    pub fn new(steps: impl Into<Vec<Step>>, window: Span) -> Self {
+        Self {
+            steps: steps.into(),
+            window,
+        }
+    }
+}
+
+/// Which way a player faces; combos are written facing right.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Facing {
+    #[default]
+    Right,
+    Left,
+}
+
+/// A combo completed during an update.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ComboMatch<C> {
+    pub player: Player,
+    pub combo: C,
+}
+
+#[derive(Default)]
+struct PlayerHistory {
+    previous: PlayerState,
+    facing: Facing,
+    inputs: VecDeque<(Step, Tick)>,
+}
+
+impl PlayerHistory {
+    /// Records this frame's inputs, returning how many were added.
+    fn record(&mut self, current: PlayerState, now: Tick) -> usize {
+        let before = self.inputs.len();
+        let mirror = self.facing == Facing::Left;
+
+        let direction = current.direction();
+        if direction != self.previous.direction() {
+            let direction = if mirror {
+                direction.mirrored()
+            } else {
+                direction
+            };
+            self.inputs.push_back((Step::Direction(direction), now));
+        }
+
+        for button in Button::ALL {
+            let pressed = current.is_pressed(button);
+            if pressed == self.previous.is_pressed(button) {
+                continue;
+            }
+
+            let button = if mirror { mirrored(button) } else { button };
+            let step = if pressed {
+                Step::Press(button)
+            } else {
+                Step::Release(button)
+            };
+            self.inputs.push_back((step, now));
+        }
+
+        self.previous = current;
+        self.inputs.len() - before
+    }
+
+    /// Whether `combo` ends with one of the newest `added` inputs, with its earlier steps in
+    /// order before it and all of them inside the window.
+    fn completes(&self, combo: &Combo, added: usize, now: Tick) -> bool {
+        let mut steps = combo.steps.iter().rev();
+        let Some(mut wanted) = steps.next() else {
+            return false;
+        };
+        let mut started = false;
+
+        for (age, (step, tick)) in self.inputs.iter().rev().enumerate() {
+            if !started && age >= added {
+                return false;
+            }
+            if !combo.window.contains(*tick, now) {
+                return false;
+            }
+            if step != wanted {
+                continue;
+            }
+
+            started = true;
+            match steps.next() {
+                Some(next) => wanted = next,
+                None => return true,
+            }
+        }
+
+        false
+    }
+}
+
+fn mirrored(button: Button) -> Button {
+    match button {
+        Button::Left => Button::Right,
+        Button::Right => Button::Left,
+        other => other,
+    }
+}
+
+/// Watches both players' inputs for any of a set of combos, identified by `C`.
+pub struct ComboRecognizer<C> {
+    combos: Vec<(C, Combo)>,
+    players: [PlayerHistory; 2],
+    clock: Clock,
+}
+
+impl<C: Copy> Default for ComboRecognizer<C> {
+    fn default() -> Self {
+        Self {
+            combos: Vec::new(),
+            players: Default::default(),
+            clock: Clock::default(),
+        }
+    }
+}
+
+impl<C: Copy> ComboRecognizer<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a combo, for building the full list in one expression.
+    pub fn with(mut self, id: C, combo: Combo) -> Self {
+        self.add(id, combo);
+        self
+    }
+
+    pub fn add(&mut self, id: C, combo: Combo) {
+        self.combos.push((id, combo));
+    }
+
+    pub fn set_facing(&mut self, player: Player, facing: Facing) {
+        self.history_mut(player).facing = facing;
+    }
+
+    /// Forgets a player's recent inputs, for example after a round ends.
+    pub fn clear(&mut self, player: Player) {
+        self.history_mut(player).inputs.clear();
+    }
+
+    /// Feeds one frame of input and returns the combos it completed.
+    ///
+    /// A player's input history is cleared once they complete a combo, so the same inputs never
+    /// trigger twice.
+    pub fn update(&mut self, state: &ControllerState, now_ms: f64) -> Vec<ComboMatch<C>> {
+        let now = self.clock.tick(now_ms);
+        let mut matches = Vec::new();
+
+        for player in Player::ALL {
+            let history = &mut self.players[player.index()];
+            let added = history.record(state.player(player), now);
+
+            let count = matches.len();
+            if added > 0 {
+                for (id, combo) in &self.combos {
+                    if history.completes(combo, added, now) {
+                        matches.push(ComboMatch { player, combo: *id });
+                    }
+                }
+            }
+
+            if matches.len() > count {
+                history.inputs.clear();
+            } else {
+                let combos = &self.combos;
+                history.inputs.retain(|(_, tick)| {
+                    combos
+                        .iter()
+                        .any(|(_, combo)| combo.window.contains(*tick, now))
+                });
+                while history.inputs.len() > MAX_HISTORY {
+                    history.inputs.pop_front();
+                }
+            }
+        }
+
+        matches
+    }
+
+    fn history_mut(&mut self, player: Player) -> &mut PlayerHistory {
+        &mut self.players[player.index()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::test_states::holding;
+
+    fn hadouken() -> ComboRecognizer<&'static str> {
+        ComboRecognizer::new().with(
+            "hadouken",
+            Combo::new(
+                [
+                    Step::Direction(Direction::Down),
+                    Step::Direction(Direction::DownRight),
+                    Step::Direction(Direction::Right),
+                    Step::Press(Button::A),
+                ],
+                Span::Frames(20),
+            ),
+        )
+    }
+
+    /// Feeds one frame per state and returns the names of everything matched.
+    fn feed(
+        recognizer: &mut ComboRecognizer<&'static str>,
+        frames: &[ControllerState],
+    ) -> Vec<&'static str> {
+        frames
+            .iter()
+            .flat_map(|state| recognizer.update(state, 0.0))
+            .map(|matched| matched.combo)
+            .collect()
+    }
+
+    fn quarter_circle(frames_per_step: usize, forward: Button) -> Vec<ControllerState> {
+        [
+            holding(&[Button::Down]),
+            holding(&[Button::Down, forward]),
+            holding(&[forward]),
+            holding(&[forward, Button::A]),
+        ]
+        .into_iter()
+        .flat_map(|state| std::iter::repeat_n(state, frames_per_step))
+        .collect()
+    }
+
+    #[test]
+    fn matches_a_motion_within_the_window() {
+        let mut recognizer = hadouken();
+        assert_eq!(
+            feed(&mut recognizer, &quarter_circle(2, Button::Right)),
+            ["hadouken"]
+        );
+    }
+
+    #[test]
+    fn a_motion_that_takes_too_long_times_out() {
+        let mut recognizer = hadouken();
+        assert!(feed(&mut recognizer, &quarter_circle(10, Button::Right)).is_empty());
+    }
+
+    #[test]
+    fn steps_out_of_order_do_not_match() {
+        let mut recognizer = hadouken();
+        let frames = [
+            holding(&[Button::Right]),
+            holding(&[Button::Down, Button::Right]),
+            holding(&[Button::Down]),
+            holding(&[Button::Down, Button::A]),
+        ];
+        assert!(feed(&mut recognizer, &frames).is_empty());
+    }
+
+    #[test]
+    fn facing_left_mirrors_forward() {
+        let mut recognizer = hadouken();
+        recognizer.set_facing(Player::One, Facing::Left);
+
+        assert!(feed(&mut recognizer, &quarter_circle(1, Button::Right)).is_empty());
+        recognizer.clear(Player::One);
+        assert_eq!(
+            feed(&mut recognizer, &quarter_circle(1, Button::Left)),
+            ["hadouken"]
+        );
+    }
+
+    #[test]
+    fn a_completed_combo_does_not_trigger_again() {
+        let mut recognizer = hadouken();
+        let mut frames = quarter_circle(1, Button::Right);
+        frames.push(holding(&[Button::Right]));
+        frames.push(holding(&[Button::Right, Button::A]));
+
+        assert_eq!(feed(&mut recognizer, &frames), ["hadouken"]);
+    }
+}
@@ -0,0 +1,510 @@
+use core::fmt;
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::{Rc, Weak};
+use std::task::{Context, Poll, Waker};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+use crate::state::{Button, Player, SystemButton};
+
+/// Which moment of an input to listen for (mirrors the JS client's event names).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EventType {
+    /// Fired when an input is pressed.
+    Press,
+    /// Fired when an input is pressed, before `Press`.
+    InputStart,
+    /// Fired when an input is released.
+    InputEnd,
+}
+
+/// Whether an input is a player button or a system button.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum InputKind {
+    Button,
+    System,
+}
+
+/// A single physical input on the cabinet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Input {
+    Button(Player, Button),
+    System(SystemButton),
+}
+
+impl Input {
+    /// Every input on the cabinet, system buttons first.
+    pub fn all() -> impl Iterator<Item = Input> {
+        let system = SystemButton::ALL.into_iter().map(Input::System);
+        let buttons = Player::ALL.into_iter().flat_map(|player| {
+            Button::ALL
+                .into_iter()
+                .map(move |button| Input::Button(player, button))
+        });
+
+        system.chain(buttons)
+    }
+
+    pub fn kind(&self) -> InputKind {
+        match self {
+            Input::Button(..) => InputKind::Button,
+            Input::System(_) => InputKind::System,
+        }
+    }
+
+    /// The player this input belongs to, `None` for system buttons.
+    pub fn player(&self) -> Option<Player> {
+        match self {
+            Input::Button(player, _) => Some(*player),
+            Input::System(_) => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InputEvent {
+    pub input: Input,
+    pub pressed: bool,
+}
+
+/// E.g. `P1 a`, `1P`.
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Input::Button(player, button) => write!(f, "{player} {button}"),
+            Input::System(button) => write!(f, "{button}"),
+        }
+    }
+}
+
+/// E.g. `P1 a pressed`, `1P released`.
+impl fmt::Display for InputEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = if self.pressed { "pressed" } else { "released" };
+        write!(f, "{} {action}", self.input)
+    }
+}
+
+impl InputEvent {
+    /// Parses a `{ type, player, button, pressed }` message as sent by the host plugin.
+    pub(crate) fn from_message(data: &JsValue) -> Option<Self> {
+        let get = |key: &str| js_sys::Reflect::get(data, &JsValue::from_str(key)).ok();
+
+        let kind = get("type")?.as_string()?;
+        let button = get("button")?.as_string()?;
+        let pressed = get("pressed")?.as_bool()?;
+
+        let input = match kind.as_str() {
+            "button" => {
+                let player = Player::from_number(get("player")?.as_f64()? as u8)?;
+                let button = match button.as_str() {
+                    "UP" => Button::Up,
+                    "DOWN" => Button::Down,
+                    "LEFT" => Button::Left,
+                    "RIGHT" => Button::Right,
+                    "A" => Button::A,
+                    "B" => Button::B,
+                    _ => return None,
+                };
+                Input::Button(player, button)
+            }
+            "system" => match button.as_str() {
+                "ONE_PLAYER" => Input::System(SystemButton::OnePlayer),
+                "TWO_PLAYER" => Input::System(SystemButton::TwoPlayer),
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        Some(InputEvent { input, pressed })
+    }
+}
+
+/// Narrows which events a listener receives. `None` fields match anything.
+///
+/// Player and button filters never match system buttons, the same as in the JS client.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    pub kind: Option<InputKind>,
+    pub player: Option<Player>,
+    pub button: Option<Button>,
+    pub system: Option<SystemButton>,
+}
+
+impl EventFilter {
+    /// Matches exactly one input.
+    pub fn input(input: Input) -> Self {
+        match input {
+            Input::Button(player, button) => EventFilter {
+                kind: Some(InputKind::Button),
+                player: Some(player),
+                button: Some(button),
+                system: None,
+            },
+            Input::System(system) => EventFilter {
+                kind: Some(InputKind::System),
+                system: Some(system),
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn matches(&self, event: &InputEvent) -> bool {
+        let kind_match = self.kind.is_none_or(|kind| kind == event.input.kind());
+        let player_match = self
+            .player
+            .is_none_or(|player| event.input.player() == Some(player));
+        let button_match = self.button.is_none_or(
+            |button| matches!(event.input, Input::Button(_, pressed) if pressed == button),
+        );
+        let system_match = self.system.is_none_or(
+            |system| matches!(event.input, Input::System(pressed) if pressed == system),
+        );
+
+        kind_match && player_match && button_match && system_match
+    }
+}
+
+type Callback = Rc<RefCell<dyn FnMut(&InputEvent)>>;
+
+struct Listener {
+    id: u32,
+    event: EventType,
+    filter: EventFilter,
+    once: bool,
+    callback: Callback,
+}
+
+#[derive(Default)]
+pub(crate) struct Listeners {
+    next_id: u32,
+    listeners: Vec<Listener>,
+}
+
+impl Listeners {
+    pub(crate) fn subscribe(
+        this: &Rc<RefCell<Self>>,
+        event: EventType,
+        filter: EventFilter,
+        once: bool,
+        callback: impl FnMut(&InputEvent) + 'static,
+    ) -> Subscription {
+        let mut listeners = this.borrow_mut();
+        let id = listeners.next_id;
+        listeners.next_id += 1;
+        listeners.listeners.push(Listener {
+            id,
+            event,
+            filter,
+            once,
+            callback: Rc::new(RefCell::new(callback)),
+        });
+
+        Subscription {
+            id,
+            listeners: Rc::downgrade(this),
+        }
+    }
+
+    fn remove(&mut self, id: u32) {
+        self.listeners.retain(|listener| listener.id != id);
+    }
+
+    /// Runs every listener interested in `event`.
+    ///
+    /// Matching callbacks are collected before any of them run, so callbacks are free to
+    /// subscribe or unsubscribe while being dispatched.
+    pub(crate) fn emit(this: &Rc<RefCell<Self>>, event: InputEvent) {
+        let types: &[EventType] = if event.pressed {
+            &[EventType::InputStart, EventType::Press]
+        } else {
+            &[EventType::InputEnd]
+        };
+
+        for ty in types {
+            let callbacks: Vec<Callback> = {
+                let mut listeners = this.borrow_mut();
+                let callbacks = listeners
+                    .listeners
+                    .iter()
+                    .filter(|listener| listener.event == *ty && listener.filter.matches(&event))
+                    .map(|listener| listener.callback.clone())
+                    .collect();
+                listeners.listeners.retain(|listener| {
+                    !(listener.once && listener.event == *ty && listener.filter.matches(&event))
+                });
+                callbacks
+            };
+
+            for callback in callbacks {
+                (callback.borrow_mut())(&event);
+            }
+        }
+    }
+}
+
+/// Handle to a registered listener.
+///
+/// Dropping it leaves the listener registered; call [`Subscription::unsubscribe`] to remove it.
+pub struct Subscription {
+    id: u32,
+    listeners: Weak<RefCell<Listeners>>,
+}
+
+impl Subscription {
+    pub fn unsubscribe(self) {
+        if let Some(listeners) = self.listeners.upgrade() {
+            listeners.borrow_mut().remove(self.id);
+        }
+    }
+}
+
+#[derive(Default)]
+struct OnceSlot {
+    event: Option<InputEvent>,
+    waker: Option<Waker>,
+}
+
+/// Future returned by `ClassicController::once`, resolving with the first matching event.
+///
+/// Dropping it before it resolves removes the underlying listener.
+pub struct Once {
+    slot: Rc<RefCell<OnceSlot>>,
+    subscription: Option<Subscription>,
+}
+
+impl Once {
+    pub(crate) fn new(
+        listeners: &Rc<RefCell<Listeners>>,
+        event: EventType,
+        filter: EventFilter,
+    ) -> Self {
+        let slot = Rc::new(RefCell::new(OnceSlot::default()));
+        let target = slot.clone();
+
+        let subscription =
+            Listeners::subscribe(listeners, event, filter, true, move |event: &InputEvent| {
+                let mut slot = target.borrow_mut();
+                slot.event = Some(*event);
+                if let Some(waker) = slot.waker.take() {
+                    waker.wake();
+                }
+            });
+
+        Once {
+            slot,
+            subscription: Some(subscription),
+        }
+    }
+}
+
+impl Future for Once {
+    type Output = InputEvent;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let mut slot = this.slot.borrow_mut();
+
+        match slot.event.take() {
+            Some(event) => {
+                this.subscription = None;
+                Poll::Ready(event)
+            }
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for Once {
+    fn drop(&mut self) {
+        if let Some(subscription) = self.subscription.take() {
+            subscription.unsubscribe();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(input: Input) -> InputEvent {
+        InputEvent {
+            input,
+            pressed: true,
+        }
+    }
+
+    /// Subscribes a listener that appends `label` to `log` whenever it fires.
+    fn log_to(
+        listeners: &Rc<RefCell<Listeners>>,
+        event: EventType,
+        filter: EventFilter,
+        once: bool,
+        log: &Rc<RefCell<Vec<&'static str>>>,
+        label: &'static str,
+    ) -> Subscription {
+        let log = log.clone();
+        Listeners::subscribe(listeners, event, filter, once, move |_| {
+            log.borrow_mut().push(label)
+        })
+    }
+
+    #[test]
+    fn filters_match_by_kind_player_and_button() {
+        let p1_a = press(Input::Button(Player::One, Button::A));
+        let p2_a = press(Input::Button(Player::Two, Button::A));
+        let one_player = press(Input::System(SystemButton::OnePlayer));
+
+        let cases = [
+            (EventFilter::default(), [true, true, true]),
+            (EventFilter::input(p1_a.input), [true, false, false]),
+            (
+                EventFilter {
+                    kind: Some(InputKind::Button),
+                    ..Default::default()
+                },
+                [true, true, false],
+            ),
+            (
+                EventFilter {
+                    kind: Some(InputKind::System),
+                    ..Default::default()
+                },
+                [false, false, true],
+            ),
+            (
+                EventFilter {
+                    button: Some(Button::A),
+                    ..Default::default()
+                },
+                [true, true, false],
+            ),
+            (EventFilter::input(one_player.input), [false, false, true]),
+        ];
+
+        for (filter, expected) in cases {
+            let matched = [p1_a, p2_a, one_player].map(|event| filter.matches(&event));
+            assert_eq!(matched, expected, "{filter:?}");
+        }
+    }
+
+    #[test]
+    fn player_and_button_filters_never_match_system_buttons() {
+        for system in SystemButton::ALL {
+            let event = press(Input::System(system));
+            for player in Player::ALL {
+                let filter = EventFilter {
+                    player: Some(player),
+                    ..Default::default()
+                };
+                assert!(!filter.matches(&event), "{player} matched {system}");
+            }
+            for button in Button::ALL {
+                let filter = EventFilter {
+                    button: Some(button),
+                    ..Default::default()
+                };
+                assert!(!filter.matches(&event), "{button} matched {system}");
+            }
+        }
+    }
+
+    #[test]
+    fn input_start_fires_before_press_and_input_end_on_release() {
+        let listeners = Rc::default();
+        let log = Rc::default();
+        let any = EventFilter::default();
+        // Subscribed in the opposite order to the one they should fire in.
+        log_to(&listeners, EventType::Press, any, false, &log, "press");
+        log_to(&listeners, EventType::InputEnd, any, false, &log, "end");
+        log_to(&listeners, EventType::InputStart, any, false, &log, "start");
+
+        let event = press(Input::Button(Player::One, Button::B));
+        Listeners::emit(&listeners, event);
+        assert_eq!(*log.borrow(), ["start", "press"]);
+
+        Listeners::emit(
+            &listeners,
+            InputEvent {
+                pressed: false,
+                ..event
+            },
+        );
+        assert_eq!(*log.borrow(), ["start", "press", "end"]);
+    }
+
+    #[test]
+    fn once_listeners_are_removed_after_firing() {
+        let listeners = Rc::default();
+        let log = Rc::default();
+        let a = EventFilter::input(Input::Button(Player::One, Button::A));
+        log_to(&listeners, EventType::Press, a, true, &log, "once");
+        log_to(&listeners, EventType::Press, a, false, &log, "always");
+
+        // A non-matching event leaves the once listener in place.
+        Listeners::emit(&listeners, press(Input::Button(Player::One, Button::B)));
+        assert_eq!(listeners.borrow().listeners.len(), 2);
+
+        Listeners::emit(&listeners, press(Input::Button(Player::One, Button::A)));
+        Listeners::emit(&listeners, press(Input::Button(Player::One, Button::A)));
+        assert_eq!(*log.borrow(), ["once", "always", "always"]);
+        assert_eq!(listeners.borrow().listeners.len(), 1);
+    }
+
+    #[test]
+    fn unsubscribe_removes_the_listener() {
+        let listeners = Rc::default();
+        let log = Rc::default();
+        let subscription = log_to(
+            &listeners,
+            EventType::Press,
+            EventFilter::default(),
+            false,
+            &log,
+            "press",
+        );
+
+        subscription.unsubscribe();
+        Listeners::emit(&listeners, press(Input::System(SystemButton::OnePlayer)));
+        assert!(log.borrow().is_empty());
+    }
+
+    #[test]
+    fn callbacks_can_subscribe_and_unsubscribe_during_dispatch() {
+        let listeners: Rc<RefCell<Listeners>> = Rc::default();
+        let log: Rc<RefCell<Vec<&'static str>>> = Rc::default();
+        let any = EventFilter::default();
+
+        let victim = log_to(&listeners, EventType::Press, any, false, &log, "victim");
+        let victim = RefCell::new(Some(victim));
+        let (weak, inner_log) = (Rc::downgrade(&listeners), log.clone());
+        Listeners::subscribe(&listeners, EventType::InputStart, any, true, move |_| {
+            inner_log.borrow_mut().push("start");
+            if let Some(victim) = victim.borrow_mut().take() {
+                victim.unsubscribe();
+            }
+            let listeners = weak.upgrade().unwrap();
+            log_to(
+                &listeners,
+                EventType::Press,
+                any,
+                false,
+                &inner_log,
+                "added",
+            );
+        });
+
+        Listeners::emit(&listeners, press(Input::System(SystemButton::TwoPlayer)));
+        assert_eq!(*log.borrow(), ["start", "added"]);
+    }
+}
@@ -0,0 +1,198 @@
+//! Keyboard and gamepad input for when no RCade host is present, for example when the game runs
+//! from a plain dev server in a browser.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use web_sys::{Gamepad, GamepadButton, KeyboardEvent, Navigator, Window};
+
+use crate::events::{Input, Listeners};
+use crate::state::{Button, ControllerState, Player, SystemButton};
+
+/// Same default mapping as the host plugin (`plugins/input-classic/index.ts`).
+const KEY_MAP: [(&str, Input); 14] = [
+    ("KeyW", Input::Button(Player::One, Button::Up)),
+    ("KeyS", Input::Button(Player::One, Button::Down)),
+    ("KeyA", Input::Button(Player::One, Button::Left)),
+    ("KeyD", Input::Button(Player::One, Button::Right)),
+    ("KeyF", Input::Button(Player::One, Button::A)),
+    ("KeyG", Input::Button(Player::One, Button::B)),
+    ("KeyI", Input::Button(Player::Two, Button::Up)),
+    ("KeyK", Input::Button(Player::Two, Button::Down)),
+    ("KeyJ", Input::Button(Player::Two, Button::Left)),
+    ("KeyL", Input::Button(Player::Two, Button::Right)),
+    ("Semicolon", Input::Button(Player::Two, Button::A)),
+    ("Quote", Input::Button(Player::Two, Button::B)),
+    ("Digit1", Input::System(SystemButton::OnePlayer)),
+    ("Digit2", Input::System(SystemButton::TwoPlayer)),
+];
+
+/// Button indices of the W3C "standard" gamepad mapping.
+const GAMEPAD_BUTTONS: [(u32, Button); 6] = [
+    (12, Button::Up),
+    (13, Button::Down),
+    (14, Button::Left),
+    (15, Button::Right),
+    (0, Button::A),
+    (1, Button::B),
+];
+const GAMEPAD_START: u32 = 9;
+const AXIS_THRESHOLD: f64 = 0.5;
+const GAMEPAD_POLL_MS: i32 = 16;
+
+type KeyClosure = Closure<dyn FnMut(KeyboardEvent)>;
+
+struct Shared {
+    keyboard: Cell<ControllerState>,
+    gamepads: Cell<ControllerState>,
+    listeners: Rc<RefCell<Listeners>>,
+}
+
+impl Shared {
+    fn state(&self) -> ControllerState {
+        let bits = self.keyboard.get().to_bits() | self.gamepads.get().to_bits();
+        ControllerState {
+            connected: true,
+            ..ControllerState::from_bits(bits)
+        }
+    }
+
+    /// Replaces the state of one source, firing events for inputs whose combined state changed.
+    fn update(&self, source: &Cell<ControllerState>, next: ControllerState) {
+        let before = self.state();
+        source.set(next);
+
+        for event in self.state().diff(&before) {
+            Listeners::emit(&self.listeners, event);
+        }
+    }
+}
+
+pub(crate) struct BrowserInput {
+    shared: Rc<Shared>,
+    window: Window,
+    on_key_down: KeyClosure,
+    on_key_up: KeyClosure,
+    _poll: Closure<dyn FnMut()>,
+    poll_handle: i32,
+}
+
+impl BrowserInput {
+    pub(crate) fn new(listeners: Rc<RefCell<Listeners>>) -> Result<Self, JsValue> {
+        let window =
+            web_sys::window().ok_or_else(|| JsValue::from_str("browser input needs a window"))?;
+
+        let shared = Rc::new(Shared {
+            keyboard: Cell::new(ControllerState::default()),
+            gamepads: Cell::new(ControllerState::default()),
+            listeners,
+        });
+
+        let key_handler = |pressed: bool| {
+            let shared = shared.clone();
+            Closure::wrap(Box::new(move |event: KeyboardEvent| {
+                if event.repeat() {
+                    return;
+                }
+
+                let code = event.code();
+                if let Some((_, input)) = KEY_MAP.iter().find(|(key, _)| *key == code) {
+                    let mut next = shared.keyboard.get();
+                    next.set_input_pressed(*input, pressed);
+                    shared.update(&shared.keyboard, next);
+                }
+            }) as Box<dyn FnMut(KeyboardEvent)>)
+        };
+
+        let on_key_down = key_handler(true);
+        let on_key_up = key_handler(false);
+        window.add_event_listener_with_callback("keydown", on_key_down.as_ref().unchecked_ref())?;
+        window.add_event_listener_with_callback("keyup", on_key_up.as_ref().unchecked_ref())?;
+
+        // Gamepads have no change events, so poll them
+        let navigator = window.navigator();
+        let poll_shared = shared.clone();
+        let poll = Closure::wrap(Box::new(move || {
+            poll_shared.update(&poll_shared.gamepads, read_gamepads(&navigator));
+        }) as Box<dyn FnMut()>);
+        let poll_handle = window.set_interval_with_callback_and_timeout_and_arguments_0(
+            poll.as_ref().unchecked_ref(),
+            GAMEPAD_POLL_MS,
+        )?;
+
+        Ok(Self {
+            shared,
+            window,
+            on_key_down,
+            on_key_up,
+            _poll: poll,
+            poll_handle,
+        })
+    }
+
+    pub(crate) fn state(&self) -> ControllerState {
+        self.shared.state()
+    }
+}
+
+impl Drop for BrowserInput {
+    fn drop(&mut self) {
+        let _ = self.window.remove_event_listener_with_callback(
+            "keydown",
+            self.on_key_down.as_ref().unchecked_ref(),
+        );
+        let _ = self
+            .window
+            .remove_event_listener_with_callback("keyup", self.on_key_up.as_ref().unchecked_ref());
+        self.window.clear_interval_with_handle(self.poll_handle);
+    }
+}
+
+/// Reads the first two gamepads as players one and two. Start acts as that player's system button.
+fn read_gamepads(navigator: &Navigator) -> ControllerState {
+    let mut state = ControllerState::default();
+    let Ok(gamepads) = navigator.get_gamepads() else {
+        return state;
+    };
+
+    for (player, gamepad) in Player::ALL.into_iter().zip(gamepads.iter()) {
+        let Ok(gamepad) = gamepad.dyn_into::<Gamepad>() else {
+            continue;
+        };
+
+        let buttons = gamepad.buttons();
+        let pressed = |index: u32| {
+            buttons
+                .get(index)
+                .dyn_into::<GamepadButton>()
+                .is_ok_and(|button| button.pressed())
+        };
+
+        for (index, button) in GAMEPAD_BUTTONS {
+            if pressed(index) {
+                state.set_pressed(player, button, true);
+            }
+        }
+
+        let axes = gamepad.axes();
+        let axis = |index: u32| axes.get(index).as_f64().unwrap_or(0.0);
+        if axis(0) < -AXIS_THRESHOLD {
+            state.set_pressed(player, Button::Left, true);
+        } else if axis(0) > AXIS_THRESHOLD {
+            state.set_pressed(player, Button::Right, true);
+        }
+        if axis(1) < -AXIS_THRESHOLD {
+            state.set_pressed(player, Button::Up, true);
+        } else if axis(1) > AXIS_THRESHOLD {
+            state.set_pressed(player, Button::Down, true);
+        }
+
+        if pressed(GAMEPAD_START) {
+            state.set_input_pressed(Input::System(SystemButton::for_player(player)), true);
+        }
+    }
+
+    state
+}
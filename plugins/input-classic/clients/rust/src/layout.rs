@@ -0,0 +1,182 @@
+//! Buttons beyond the standard layout.
+//!
+//! The host answers a `get_config` request with every button the cabinet has. Buttons outside the
+//! standard d-pad, A, B and player-select set (start, coin, C/D, ...) are numbered in the order
+//! the host lists them and stored in [`ControllerState::extra_bits`](crate::state::ControllerState::extra_bits),
+//! so they can be read without a client update that knows about them.
+
+use wasm_bindgen::JsValue;
+
+use crate::state::Player;
+
+/// A button the host advertised that is not part of the standard layout.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ExtraButton {
+    /// The player the button belongs to, `None` for buttons shared by both players.
+    pub player: Option<Player>,
+    /// The name the host uses for the button, e.g. `"START"` or `"COIN"`.
+    pub name: String,
+}
+
+impl ExtraButton {
+    /// The button described by the `type`, `player` and `button` fields of a host entry, or
+    /// `None` if the entry doesn't name a player or system button.
+    ///
+    /// The worker only numbers entries this accepts, so the layout's indices line up with the
+    /// bits of [`ControllerState::extra_bits`](crate::state::ControllerState::extra_bits).
+    fn from_host(kind: &str, player: Option<f64>, name: String) -> Option<Self> {
+        let player = match kind {
+            "button" if player == Some(1.0) => Some(Player::One),
+            "button" if player == Some(2.0) => Some(Player::Two),
+            "system" => None,
+            _ => return None,
+        };
+
+        Some(ExtraButton { player, name })
+    }
+}
+
+/// The extra buttons of the connected cabinet. Empty until the host has answered, and for hosts
+/// that predate button advertising.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Layout {
+    extras: Vec<ExtraButton>,
+}
+
+impl Layout {
+    /// Extra buttons that fit in the packed state; any further buttons are ignored.
+    pub const MAX_EXTRA_BUTTONS: usize = 17;
+
+    pub fn new(mut extras: Vec<ExtraButton>) -> Self {
+        extras.truncate(Self::MAX_EXTRA_BUTTONS);
+        Self { extras }
+    }
+
+    /// The extra buttons, in index order.
+    pub fn extras(&self) -> &[ExtraButton] {
+        &self.extras
+    }
+
+    /// Index of the named button, for [`ControllerState::is_extra_pressed`].
+    ///
+    /// [`ControllerState::is_extra_pressed`]: crate::state::ControllerState::is_extra_pressed
+    pub fn extra_index(&self, player: Option<Player>, name: &str) -> Option<usize> {
+        self.extras
+            .iter()
+            .position(|extra| extra.player == player && extra.name == name)
+    }
+
+    /// Number of extra buttons for the player with the most of them.
+    pub(crate) fn extra_buttons_per_player(&self) -> usize {
+        Player::ALL
+            .into_iter()
+            .map(|player| {
+                self.extras
+                    .iter()
+                    .filter(|extra| extra.player == Some(player))
+                    .count()
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub(crate) fn extra_system_buttons(&self) -> usize {
+        self.extras
+            .iter()
+            .filter(|extra| extra.player.is_none())
+            .count()
+    }
+
+    /// Parses a `{ type: "layout", extras }` message as sent by the worker.
+    pub(crate) fn from_message(data: &JsValue) -> Option<Self> {
+        let get =
+            |value: &JsValue, key: &str| js_sys::Reflect::get(value, &JsValue::from_str(key)).ok();
+
+        if get(data, "type")?.as_string()? != "layout" {
+            return None;
+        }
+
+        let extras = js_sys::Array::from(&get(data, "extras")?)
+            .iter()
+            .filter_map(|extra| {
+                let kind = get(&extra, "type")?.as_string()?;
+                let player = get(&extra, "player").and_then(|player| player.as_f64());
+                let name = get(&extra, "button")?.as_string()?;
+
+                ExtraButton::from_host(&kind, player, name)
+            })
+            .collect();
+
+        Some(Layout::new(extras))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::ControllerState;
+
+    fn extra(player: Option<Player>, name: &str) -> ExtraButton {
+        ExtraButton {
+            player,
+            name: name.into(),
+        }
+    }
+
+    #[test]
+    fn parses_player_and_system_entries() {
+        let cases = [
+            ("button", Some(1.0), Some(Some(Player::One))),
+            ("button", Some(2.0), Some(Some(Player::Two))),
+            ("system", None, Some(None)),
+            // The player of a system button is ignored, as in the worker.
+            ("system", Some(1.0), Some(None)),
+            ("button", None, None),
+            ("button", Some(0.0), None),
+            ("button", Some(3.0), None),
+            ("button", Some(1.5), None),
+            ("spinner", Some(1.0), None),
+        ];
+
+        for (kind, player, expected) in cases {
+            assert_eq!(
+                ExtraButton::from_host(kind, player, "START".into()),
+                expected.map(|player| extra(player, "START")),
+                "{kind} {player:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn indices_follow_the_advertised_order() {
+        let layout = Layout::new(vec![
+            extra(Some(Player::One), "START"),
+            extra(None, "COIN"),
+            extra(Some(Player::Two), "START"),
+        ]);
+
+        assert_eq!(layout.extra_index(Some(Player::One), "START"), Some(0));
+        assert_eq!(layout.extra_index(None, "COIN"), Some(1));
+        assert_eq!(layout.extra_index(Some(Player::Two), "START"), Some(2));
+        assert_eq!(layout.extra_index(None, "START"), None);
+        assert_eq!(layout.extra_buttons_per_player(), 1);
+        assert_eq!(layout.extra_system_buttons(), 1);
+    }
+
+    #[test]
+    fn every_index_has_a_bit_in_the_packed_state() {
+        let extras = (0..Layout::MAX_EXTRA_BUTTONS + 3)
+            .map(|i| extra(None, &format!("EXTRA{i}")))
+            .collect();
+        let layout = Layout::new(extras);
+        assert_eq!(layout.extras().len(), Layout::MAX_EXTRA_BUTTONS);
+
+        // The worker sets bit 15 + i for the i-th extra.
+        for (index, button) in layout.extras().iter().enumerate() {
+            let state = ControllerState::from_bits(1 << (15 + index));
+            assert_eq!(layout.extra_index(None, &button.name), Some(index));
+            assert!(state.is_extra_pressed(index));
+            assert_eq!(state.extra_bits().count_ones(), 1);
+        }
+    }
+}
@@ -1,59 +1,210 @@
+pub mod buffer;
+pub mod combo;
+pub mod events;
+mod fallback;
+pub mod layout;
+pub mod mapping;
+pub mod modifiers;
+pub mod repeat;
+pub mod session;
+pub mod slots;
+pub mod socd;
 pub mod state;
+pub mod timing;
 
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rcade_sdk::clock;
+use rcade_sdk::device::{Capabilities, InputDevice};
 use rcade_sdk::{channel::PluginChannel, shmem_runner::PluginSharedMemoryRunner};
 use wasm_bindgen::JsValue;
 
-use crate::state::ControllerState;
-
-const CONNECTED: i32 = 0;
-const SYSTEM_ONE_PLAYER: i32 = 1;
-const SYSTEM_TWO_PLAYER: i32 = 2;
-const PLAYER1_UP: i32 = 3;
-const PLAYER1_DOWN: i32 = 4;
-const PLAYER1_LEFT: i32 = 5;
-const PLAYER1_RIGHT: i32 = 6;
-const PLAYER1_A: i32 = 7;
-const PLAYER1_B: i32 = 8;
-const PLAYER2_UP: i32 = 9;
-const PLAYER2_DOWN: i32 = 10;
-const PLAYER2_LEFT: i32 = 11;
-const PLAYER2_RIGHT: i32 = 12;
-const PLAYER2_A: i32 = 13;
-const PLAYER2_B: i32 = 14;
+use crate::events::{EventFilter, EventType, InputEvent, Listeners, Once, Subscription};
+use crate::fallback::BrowserInput;
+use crate::layout::Layout;
+use crate::modifiers::{ButtonModifiers, ModifierConfig};
+use crate::state::{Button, ControllerState, Player, SystemButton};
+
+enum Backend {
+    Plugin(PluginSharedMemoryRunner),
+    Browser(BrowserInput),
+}
 
 pub struct ClassicController {
-    runner: PluginSharedMemoryRunner,
+    backend: Backend,
+    listeners: Rc<RefCell<Listeners>>,
+    layout: Rc<RefCell<Layout>>,
+    modifiers: RefCell<Option<ButtonModifiers>>,
 }
 
 impl ClassicController {
+    /// Connects to the `@rcade/input-classic` host plugin.
+    ///
+    /// Without a host (for example under `trunk serve` in a plain browser tab), this falls back to
+    /// the keyboard, using the same keys as the host plugin, and to the first two gamepads. See
+    /// [`PluginChannel::acquire_if_hosted`] for when that happens.
     pub async fn acquire() -> Result<ClassicController, JsValue> {
-        let channel = PluginChannel::acquire("@rcade/input-classic", "1.0.0").await?;
-        let runner = PluginSharedMemoryRunner::spawn(include_str!("./worker.js"), channel, 15)?;
+        let listeners = Rc::new(RefCell::new(Listeners::default()));
+        let layout = Rc::new(RefCell::new(Layout::default()));
+
+        let channel = PluginChannel::acquire_if_hosted("@rcade/input-classic", "1.0.0").await?;
+
+        let backend = match channel {
+            Some(channel) => {
+                let mut runner =
+                    PluginSharedMemoryRunner::spawn(include_str!("./worker.js"), channel, 4)?;
+
+                let dispatch = listeners.clone();
+                let host_layout = layout.clone();
+                runner.set_message_handler(move |data| {
+                    if let Some(layout) = Layout::from_message(&data) {
+                        *host_layout.borrow_mut() = layout;
+                    } else if let Some(event) = InputEvent::from_message(&data) {
+                        Listeners::emit(&dispatch, event);
+                    }
+                });
 
-        Ok(ClassicController { runner })
+                Backend::Plugin(runner)
+            }
+            None => Backend::Browser(BrowserInput::new(listeners.clone())?),
+        };
+
+        Ok(ClassicController {
+            backend,
+            listeners,
+            layout,
+            modifiers: RefCell::default(),
+        })
+    }
+
+    /// Whether input comes from the browser's keyboard and gamepads instead of the RCade host.
+    pub fn is_browser_fallback(&self) -> bool {
+        matches!(self.backend, Backend::Browser(_))
     }
 
+    /// Calls `callback` every time an event of the given type happens.
+    pub fn on(
+        &self,
+        event: EventType,
+        callback: impl FnMut(&InputEvent) + 'static,
+    ) -> Subscription {
+        self.on_filtered(event, EventFilter::default(), callback)
+    }
+
+    /// Like [`ClassicController::on`], but only for events matching `filter`.
+    pub fn on_filtered(
+        &self,
+        event: EventType,
+        filter: EventFilter,
+        callback: impl FnMut(&InputEvent) + 'static,
+    ) -> Subscription {
+        Listeners::subscribe(&self.listeners, event, filter, false, callback)
+    }
+
+    /// Removes a listener registered with `on`, `on_filtered` or `once_with`.
+    pub fn off(&self, subscription: Subscription) {
+        subscription.unsubscribe();
+    }
+
+    /// Resolves with the next event matching `filter`.
+    pub fn once(&self, event: EventType, filter: EventFilter) -> Once {
+        Once::new(&self.listeners, event, filter)
+    }
+
+    /// Calls `callback` for the next event matching `filter`, then removes itself.
+    pub fn once_with(
+        &self,
+        event: EventType,
+        filter: EventFilter,
+        callback: impl FnMut(&InputEvent) + 'static,
+    ) -> Subscription {
+        Listeners::subscribe(&self.listeners, event, filter, true, callback)
+    }
+
+    /// The buttons as they are right now, without modifiers. Reading it changes nothing, so it
+    /// can be called any number of times per frame.
     pub fn state(&self) -> ControllerState {
-        let data_view = self.runner.lock_blocking().data_view();
-
-        // web_sys::console::log_2(&JsValue::from_str("data_view"), &data_view);
-
-        ControllerState {
-            connected: data_view.at(CONNECTED).unwrap() != 0,
-            system_one_player: data_view.at(SYSTEM_ONE_PLAYER).unwrap() != 0,
-            system_two_player: data_view.at(SYSTEM_TWO_PLAYER).unwrap() != 0,
-            player1_up: data_view.at(PLAYER1_UP).unwrap() != 0,
-            player1_down: data_view.at(PLAYER1_DOWN).unwrap() != 0,
-            player1_left: data_view.at(PLAYER1_LEFT).unwrap() != 0,
-            player1_right: data_view.at(PLAYER1_RIGHT).unwrap() != 0,
-            player1_a: data_view.at(PLAYER1_A).unwrap() != 0,
-            player1_b: data_view.at(PLAYER1_B).unwrap() != 0,
-            player2_up: data_view.at(PLAYER2_UP).unwrap() != 0,
-            player2_down: data_view.at(PLAYER2_DOWN).unwrap() != 0,
-            player2_left: data_view.at(PLAYER2_LEFT).unwrap() != 0,
-            player2_right: data_view.at(PLAYER2_RIGHT).unwrap() != 0,
-            player2_a: data_view.at(PLAYER2_A).unwrap() != 0,
-            player2_b: data_view.at(PLAYER2_B).unwrap() != 0,
+        match &self.backend {
+            Backend::Plugin(runner) => {
+                let bits = js_sys::Atomics::load(&runner.atomic_view(), 0).unwrap();
+
+                ControllerState::from_bits(bits as u32)
+            }
+            Backend::Browser(browser) => browser.state(),
+        }
+    }
+
+    /// The input for this frame, with any modifiers from [`ClassicController::set_modifiers`]
+    /// applied. Each call steps the modifiers by one frame, so call this exactly once per frame;
+    /// without modifiers it returns the same as [`ClassicController::state`].
+    pub fn poll(&self) -> ControllerState {
+        let state = self.state();
+        match self.modifiers.borrow_mut().as_mut() {
+            Some(modifiers) => modifiers.update(&state, clock::now_ms()),
+            None => state,
         }
     }
+
+    /// Applies `config` to everything [`ClassicController::poll`] returns from now on, releasing
+    /// anything held by the previous configuration. [`ClassicController::state`] and events from
+    /// `on` and `once` still report the physical buttons.
+    pub fn set_modifiers(&self, config: ModifierConfig) {
+        let mut modifiers = self.modifiers.borrow_mut();
+        match modifiers.as_mut() {
+            Some(modifiers) => modifiers.set_config(config),
+            None => *modifiers = Some(ButtonModifiers::new(config)),
+        }
+    }
+
+    /// The configuration set with [`ClassicController::set_modifiers`], if any.
+    pub fn modifiers(&self) -> Option<ModifierConfig> {
+        self.modifiers
+            .borrow()
+            .as_ref()
+            .map(|modifiers| *modifiers.config())
+    }
+
+    /// The extra buttons advertised by the host, beyond the standard layout.
+    pub fn layout(&self) -> Layout {
+        self.layout.borrow().clone()
+    }
+
+    /// Whether the extra button the host calls `name` is held down. `player` is `None` for
+    /// buttons shared by both players. Always `false` for buttons the cabinet doesn't have.
+    pub fn is_extra_pressed(&self, player: Option<Player>, name: &str) -> bool {
+        let index = self.layout.borrow().extra_index(player, name);
+        index.is_some_and(|index| self.state().is_extra_pressed(index))
+    }
+}
+
+impl InputDevice for ClassicController {
+    type Frame = ControllerState;
+
+    fn connected(&self) -> bool {
+        self.state().connected
+    }
+
+    fn is_fallback(&self) -> bool {
+        self.is_browser_fallback()
+    }
+
+    fn player_count(&self) -> u8 {
+        Player::ALL.len() as u8
+    }
+
+    /// The standard buttons plus any extra buttons advertised by the host.
+    fn capabilities(&self) -> Capabilities {
+        let layout = self.layout.borrow();
+
+        Capabilities {
+            buttons_per_player: (Button::ALL.len() + layout.extra_buttons_per_player()) as u8,
+            system_buttons: (SystemButton::ALL.len() + layout.extra_system_buttons()) as u8,
+            spinners_per_player: 0,
+        }
+    }
+
+    fn update(&mut self) -> ControllerState {
+        self.poll()
+    }
 }
@@ -0,0 +1,314 @@
+//! Game-defined actions bound to cabinet inputs.
+//!
+//! Games describe what the player can do with their own action type, bind each action to one or
+//! more inputs, and query actions instead of buttons. Rebinding (for example swapping A and B for
+//! a left-handed player) then only changes the [`Bindings`], never the game code.
+//!
+//! Bindings name a player, so actions that exist for both players can use `(Player, Action)` as
+//! the action type.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::state::{Button, ControllerState, Direction, Player, SystemButton};
+
+/// Which way a spinner has to turn to trigger a binding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SpinDirection {
+    /// Negative step deltas.
+    Left,
+    /// Positive step deltas.
+    Right,
+}
+
+/// An input an action can be bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Binding {
+    /// Held while the button is held, including d-pad directions as part of a diagonal.
+    Button(Player, Button),
+    /// Held while the d-pad points exactly in this direction.
+    Direction(Player, Direction),
+    System(SystemButton),
+    /// Held on frames where the spinner moved the given way.
+    Spinner(Player, SpinDirection),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ActionBinding<A> {
+    pub action: A,
+    pub binding: Binding,
+}
+
+/// The set of bindings for every action, loadable from any serde format with the `serde` feature.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Bindings<A> {
+    bindings: Vec<ActionBinding<A>>,
+}
+
+impl<A> Default for Bindings<A> {
+    fn default() -> Self {
+        Self {
+            bindings: Vec::new(),
+        }
+    }
+}
+
+impl<A: Copy + Eq> Bindings<A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a binding, for building a default layout in one expression.
+    pub fn with(mut self, action: A, binding: Binding) -> Self {
+        self.bind(action, binding);
+        self
+    }
+
+    /// Binds `action` to `binding`, in addition to any existing bindings.
+    pub fn bind(&mut self, action: A, binding: Binding) {
+        let entry = ActionBinding { action, binding };
+        if !self.bindings.contains(&entry) {
+            self.bindings.push(entry);
+        }
+    }
+
+    /// Removes every binding of `action`.
+    pub fn unbind(&mut self, action: A) {
+        self.bindings.retain(|entry| entry.action != action);
+    }
+
+    /// Replaces every binding of `action` with `binding`.
+    pub fn rebind(&mut self, action: A, binding: Binding) {
+        self.unbind(action);
+        self.bind(action, binding);
+    }
+
+    pub fn bindings_for(&self, action: A) -> impl Iterator<Item = Binding> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |entry| entry.action == action)
+            .map(|entry| entry.binding)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ActionBinding<A>> {
+        self.bindings.iter()
+    }
+}
+
+/// Tracks which actions are active, updated once per frame from the controller state.
+pub struct ActionMap<A> {
+    bindings: Bindings<A>,
+    pressed: HashSet<A>,
+    previous: HashSet<A>,
+    steps: HashMap<A, u32>,
+}
+
+impl<A: Copy + Eq + Hash> ActionMap<A> {
+    pub fn new(bindings: Bindings<A>) -> Self {
+        Self {
+            bindings,
+            pressed: HashSet::new(),
+            previous: HashSet::new(),
+            steps: HashMap::new(),
+        }
+    }
+
+    pub fn bindings(&self) -> &Bindings<A> {
+        &self.bindings
+    }
+
+    /// Bindings can be changed at any time; the change applies from the next update.
+    pub fn bindings_mut(&mut self) -> &mut Bindings<A> {
+        &mut self.bindings
+    }
+
+    /// Updates from the classic controller only. Spinner bindings stay inactive.
+    pub fn update(&mut self, state: &ControllerState) {
+        self.update_with_spinners(state, [0, 0]);
+    }
+
+    /// Updates from the classic controller and this frame's spinner step deltas for players one
+    /// and two.
+    pub fn update_with_spinners(&mut self, state: &ControllerState, spinner_deltas: [i16; 2]) {
+        core::mem::swap(&mut self.previous, &mut self.pressed);
+        self.pressed.clear();
+        self.steps.clear();
+
+        for entry in self.bindings.iter() {
+            let active = match entry.binding {
+                Binding::Button(player, button) => state.is_pressed(player, button),
+                Binding::Direction(player, direction) => {
+                    state.player(player).direction() == direction
+                }
+                Binding::System(button) => state.is_system_pressed(button),
+                Binding::Spinner(player, spin) => {
+                    let delta = spinner_deltas[player.index()];
+                    let moved = match spin {
+                        SpinDirection::Left => (-(delta as i32)).max(0) as u32,
+                        SpinDirection::Right => (delta as i32).max(0) as u32,
+                    };
+
+                    if moved > 0 {
+                        *self.steps.entry(entry.action).or_default() += moved;
+                    }
+                    moved > 0
+                }
+            };
+
+            if active {
+                self.pressed.insert(entry.action);
+            }
+        }
+    }
+
+    /// Whether any binding of `action` is held this frame.
+    pub fn action_pressed(&self, action: A) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    /// Whether `action` became held this frame.
+    pub fn action_just_pressed(&self, action: A) -> bool {
+        self.pressed.contains(&action) && !self.previous.contains(&action)
+    }
+
+    /// Whether `action` stopped being held this frame.
+    pub fn action_just_released(&self, action: A) -> bool {
+        !self.pressed.contains(&action) && self.previous.contains(&action)
+    }
+
+    /// Spinner steps moved this frame through `action`'s spinner bindings.
+    pub fn action_steps(&self, action: A) -> u32 {
+        self.steps.get(&action).copied().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Input;
+    use crate::state::test_states::{holding, holding_inputs};
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    enum Action {
+        Jump,
+        Walk,
+        Start,
+        Turn,
+    }
+
+    fn map() -> ActionMap<Action> {
+        ActionMap::new(
+            Bindings::new()
+                .with(Action::Jump, Binding::Button(Player::One, Button::A))
+                .with(Action::Jump, Binding::Button(Player::One, Button::Up))
+                .with(
+                    Action::Walk,
+                    Binding::Direction(Player::One, Direction::Right),
+                )
+                .with(Action::Start, Binding::System(SystemButton::OnePlayer)),
+        )
+    }
+
+    #[test]
+    fn any_binding_activates_the_action() {
+        let mut map = map();
+        for buttons in [&[Button::A][..], &[Button::Up], &[Button::A, Button::Up]] {
+            map.update(&holding(buttons));
+            assert!(map.action_pressed(Action::Jump), "{buttons:?}");
+        }
+
+        map.update(&holding(&[Button::B]));
+        assert!(!map.action_pressed(Action::Jump));
+    }
+
+    #[test]
+    fn buttons_count_as_part_of_a_diagonal_but_directions_must_match_exactly() {
+        let mut map = map();
+        map.update(&holding(&[Button::Up, Button::Right]));
+        assert!(map.action_pressed(Action::Jump));
+        assert!(!map.action_pressed(Action::Walk));
+
+        map.update(&holding(&[Button::Right]));
+        assert!(map.action_pressed(Action::Walk));
+    }
+
+    #[test]
+    fn tracks_presses_and_releases_between_updates() {
+        let mut map = map();
+        let start = holding_inputs(&[Input::System(SystemButton::OnePlayer)]);
+
+        map.update(&start);
+        assert!(map.action_just_pressed(Action::Start));
+
+        map.update(&start);
+        assert!(map.action_pressed(Action::Start));
+        assert!(!map.action_just_pressed(Action::Start));
+
+        map.update(&ControllerState::default());
+        assert!(map.action_just_released(Action::Start));
+        assert!(!map.action_pressed(Action::Start));
+    }
+
+    #[test]
+    fn rebinding_replaces_every_binding_of_the_action() {
+        let mut map = map();
+        map.bindings_mut()
+            .rebind(Action::Jump, Binding::Button(Player::One, Button::B));
+        assert_eq!(
+            map.bindings()
+                .bindings_for(Action::Jump)
+                .collect::<Vec<_>>(),
+            [Binding::Button(Player::One, Button::B)]
+        );
+
+        map.update(&holding(&[Button::A]));
+        assert!(!map.action_pressed(Action::Jump));
+        map.update(&holding(&[Button::B]));
+        assert!(map.action_pressed(Action::Jump));
+    }
+
+    #[test]
+    fn binding_twice_keeps_one_binding() {
+        let bindings = Bindings::new()
+            .with(Action::Jump, Binding::Button(Player::One, Button::A))
+            .with(Action::Jump, Binding::Button(Player::One, Button::A));
+        assert_eq!(bindings.iter().count(), 1);
+    }
+
+    #[test]
+    fn spinner_bindings_count_steps_in_their_direction() {
+        let mut map = ActionMap::new(
+            Bindings::new()
+                .with(
+                    Action::Turn,
+                    Binding::Spinner(Player::Two, SpinDirection::Left),
+                )
+                .with(
+                    Action::Walk,
+                    Binding::Spinner(Player::Two, SpinDirection::Right),
+                ),
+        );
+        let idle = ControllerState::default();
+
+        map.update_with_spinners(&idle, [5, -3]);
+        assert!(map.action_pressed(Action::Turn));
+        assert_eq!(map.action_steps(Action::Turn), 3);
+        assert!(!map.action_pressed(Action::Walk));
+        assert_eq!(map.action_steps(Action::Walk), 0);
+
+        map.update_with_spinners(&idle, [0, 4]);
+        assert_eq!(map.action_steps(Action::Walk), 4);
+        assert!(map.action_just_released(Action::Turn));
+
+        map.update(&idle);
+        assert!(!map.action_pressed(Action::Walk));
+    }
+}
@@ -0,0 +1,247 @@
+//! Per-button modifiers for players who can't hold or mash buttons.
+//!
+//! [`ButtonModifiers`] rewrites each frame's [`ControllerState`] before the game sees it, so games
+//! get autofire, toggles and the like without any code of their own. The simplest way to use it
+//! is [`ClassicController::set_modifiers`](crate::ClassicController::set_modifiers), after which
+//! [`ClassicController::poll`](crate::ClassicController::poll) returns the modified input. The
+//! [`ModifierConfig`] can be set per player and saved per game with the `serde` feature.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::state::{Button, ControllerState, Player};
+use crate::timing::{Clock, Span, Tick};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Modifier {
+    /// Autofire: while held, the button is pressed for one interval and released for the next.
+    Turbo { interval: Span },
+    /// Each press switches the button between held and released.
+    Toggle,
+    /// A tap keeps the button held until the player presses another button or taps this one
+    /// again.
+    Sticky,
+    /// Every press lasts at least this long, however short the tap.
+    MinimumPress(Span),
+}
+
+/// Which modifier, if any, applies to each player's buttons.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ModifierConfig {
+    buttons: [[Option<Modifier>; Button::COUNT]; 2],
+}
+
+impl ModifierConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, player: Player, button: Button, modifier: Modifier) -> Self {
+        self.set(player, button, Some(modifier));
+        self
+    }
+
+    pub fn set(&mut self, player: Player, button: Button, modifier: Option<Modifier>) {
+        self.buttons[player.index()][button.index()] = modifier;
+    }
+
+    pub fn get(&self, player: Player, button: Button) -> Option<Modifier> {
+        self.buttons[player.index()][button.index()]
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Track {
+    /// Whether the button was physically held last frame.
+    down: bool,
+    /// When the button was last physically pressed.
+    pressed_at: Tick,
+    /// Held by the modifier regardless of the physical button (toggle, sticky, minimum press).
+    latched: bool,
+}
+
+/// Applies a [`ModifierConfig`] to each frame of input. Call [`ButtonModifiers::update`] once per
+/// frame and give the game its result.
+pub struct ButtonModifiers {
+    config: ModifierConfig,
+    tracks: [[Track; Button::COUNT]; 2],
+    clock: Clock,
+}
+
+impl ButtonModifiers {
+    pub fn new(config: ModifierConfig) -> Self {
+        Self {
+            config,
+            tracks: Default::default(),
+            clock: Clock::default(),
+        }
+    }
+
+    pub fn config(&self) -> &ModifierConfig {
+        &self.config
+    }
+
+    /// Replaces the configuration, releasing anything held by the old one.
+    pub fn set_config(&mut self, config: ModifierConfig) {
+        self.config = config;
+        for track in self.tracks.iter_mut().flatten() {
+            track.latched = false;
+        }
+    }
+
+    /// Feeds one frame of input and returns it with the modifiers applied.
+    pub fn update(&mut self, state: &ControllerState, now_ms: f64) -> ControllerState {
+        let now = self.clock.tick(now_ms);
+        let mut modified = *state;
+
+        for player in Player::ALL {
+            let tracks = &mut self.tracks[player.index()];
+            let just_pressed =
+                |button: Button| state.is_pressed(player, button) && !tracks[button.index()].down;
+            let other_pressed: [bool; Button::COUNT] = Button::ALL.map(|button| {
+                Button::ALL
+                    .into_iter()
+                    .any(|other| other != button && just_pressed(other))
+            });
+
+            for button in Button::ALL {
+                let down = state.is_pressed(player, button);
+                let track = &mut tracks[button.index()];
+                let pressed = down && !track.down;
+                if pressed {
+                    track.pressed_at = now;
+                }
+
+                let held = match self.config.get(player, button) {
+                    None => down,
+                    Some(Modifier::Turbo { interval }) => {
+                        down && interval.periods(track.pressed_at, now) % 2 == 0
+                    }
+                    Some(Modifier::Toggle) => {
+                        if pressed {
+                            track.latched = !track.latched;
+                        }
+                        track.latched
+                    }
+                    Some(Modifier::Sticky) => {
+                        if pressed {
+                            track.latched = !track.latched;
+                        } else if other_pressed[button.index()] {
+                            track.latched = false;
+                        }
+                        down || track.latched
+                    }
+                    Some(Modifier::MinimumPress(span)) => {
+                        if pressed {
+                            track.latched = true;
+                        } else if track.latched && span.elapsed(track.pressed_at, now) {
+                            track.latched = false;
+                        }
+                        down || track.latched
+                    }
+                };
+
+                track.down = down;
+                modified.set_pressed(player, button, held);
+            }
+        }
+
+        modified
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::test_states::holding;
+
+    /// Feeds one frame per entry of `held` (whether A is held) and returns whether A came out
+    /// pressed each frame.
+    fn run(modifier: Modifier, held: &[bool]) -> Vec<bool> {
+        let mut modifiers =
+            ButtonModifiers::new(ModifierConfig::new().with(Player::One, Button::A, modifier));
+        held.iter()
+            .map(|held| {
+                modifiers
+                    .update(&holding(if *held { &[Button::A] } else { &[] }), 0.0)
+                    .is_pressed(Player::One, Button::A)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn turbo_alternates_while_held() {
+        let turbo = Modifier::Turbo {
+            interval: Span::Frames(2),
+        };
+        assert_eq!(
+            run(turbo, &[true, true, true, true, true, false]),
+            [true, true, false, false, true, false]
+        );
+    }
+
+    #[test]
+    fn toggle_switches_on_each_press() {
+        assert_eq!(
+            run(Modifier::Toggle, &[true, false, false, true, false]),
+            [true, true, true, false, false]
+        );
+    }
+
+    #[test]
+    fn sticky_holds_until_another_button_is_pressed() {
+        let mut modifiers = ButtonModifiers::new(ModifierConfig::new().with(
+            Player::One,
+            Button::A,
+            Modifier::Sticky,
+        ));
+        let a = |state: ControllerState| state.is_pressed(Player::One, Button::A);
+
+        assert!(a(modifiers.update(&holding(&[Button::A]), 0.0)));
+        assert!(a(modifiers.update(&holding(&[]), 0.0)));
+
+        let mut other = ControllerState::default();
+        other.set_pressed(Player::One, Button::B, true);
+        assert!(!a(modifiers.update(&other, 0.0)));
+    }
+
+    #[test]
+    fn minimum_press_stretches_short_taps() {
+        assert_eq!(
+            run(
+                Modifier::MinimumPress(Span::Frames(3)),
+                &[true, false, false, false, false]
+            ),
+            [true, true, true, false, false]
+        );
+    }
+
+    #[test]
+    fn other_buttons_and_players_pass_through() {
+        let mut modifiers = ButtonModifiers::new(ModifierConfig::new().with(
+            Player::One,
+            Button::A,
+            Modifier::Toggle,
+        ));
+        let mut state = ControllerState::default();
+        state.set_pressed(Player::One, Button::B, true);
+        state.set_pressed(Player::Two, Button::A, true);
+
+        assert_eq!(modifiers.update(&state, 0.0), state);
+    }
+
+    #[test]
+    fn replacing_the_config_releases_latched_buttons() {
+        let mut modifiers = ButtonModifiers::new(ModifierConfig::new().with(
+            Player::One,
+            Button::A,
+            Modifier::Toggle,
+        ));
+        modifiers.update(&holding(&[Button::A]), 0.0);
+        modifiers.set_config(ModifierConfig::new());
+
+        assert_eq!(modifiers.update(&holding(&[]), 0.0), holding(&[]));
+    }
+}
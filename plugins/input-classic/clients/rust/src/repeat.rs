@@ -0,0 +1,243 @@
+//! Menu-style auto-repeat for the d-pad: move once on press, then repeat while held.
+//!
+//! Each player repeats the direction their d-pad points in as a whole, so a held diagonal repeats
+//! once per interval rather than once per axis. Changing direction counts as a new press.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::state::{ControllerState, Direction, Player};
+use crate::timing::{Clock, Span, Tick};
+
+/// Speeds up repeats the longer a direction is held.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Acceleration {
+    /// Multiplies the interval after every repeat, e.g. `0.9` for 10% faster each time.
+    pub factor: f64,
+    /// Fastest allowed interval, as a fraction of [`RepeatConfig::interval`].
+    pub min_scale: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RepeatConfig {
+    /// Time from the initial press to the first repeat.
+    pub delay: Span,
+    /// Time between repeats after that.
+    pub interval: Span,
+    pub acceleration: Option<Acceleration>,
+}
+
+impl RepeatConfig {
+    /// Interval before the next repeat, after `repeats` repeats so far.
+    fn interval_after(&self, repeats: u32) -> Span {
+        match self.acceleration {
+            None => self.interval,
+            Some(Acceleration { factor, min_scale }) => {
+                let scale = factor.powi(repeats as i32 - 1).max(min_scale);
+                self.interval.scaled(scale)
+            }
+        }
+    }
+}
+
+/// 300 ms before the first repeat, then every 80 ms.
+impl Default for RepeatConfig {
+    fn default() -> Self {
+        Self {
+            delay: Span::Millis(300.0),
+            interval: Span::Millis(80.0),
+            acceleration: None,
+        }
+    }
+}
+
+/// A navigation step, from either the initial press or a repeat.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RepeatTick {
+    pub player: Player,
+    /// Never [`Direction::Neutral`].
+    pub direction: Direction,
+    /// `false` for the initial press.
+    pub repeat: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Hold {
+    direction: Direction,
+    /// When the last tick was due.
+    last: Tick,
+    repeats: u32,
+}
+
+/// Turns held d-pad directions into navigation ticks. Each player repeats independently.
+pub struct AutoRepeat {
+    config: RepeatConfig,
+    holds: [Option<Hold>; 2],
+    clock: Clock,
+}
+
+impl AutoRepeat {
+    pub fn new(config: RepeatConfig) -> Self {
+        Self {
+            config,
+            holds: Default::default(),
+            clock: Clock::default(),
+        }
+    }
+
+    pub fn config(&self) -> &RepeatConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: RepeatConfig) {
+        self.config = config;
+    }
+
+    /// Feeds one frame of input and returns the navigation ticks it produced.
+    ///
+    /// Repeats keep to their interval however long each frame is. There is at most one tick per
+    /// player per frame, and a frame that runs late by more than a whole interval drops the
+    /// repeats it missed instead of catching up all at once.
+    pub fn update(&mut self, state: &ControllerState, now_ms: f64) -> Vec<RepeatTick> {
+        let now = self.clock.tick(now_ms);
+        let mut ticks = Vec::new();
+
+        for player in Player::ALL {
+            let direction = state.player(player).direction();
+            let hold = &mut self.holds[player.index()];
+
+            if direction == Direction::Neutral {
+                *hold = None;
+                continue;
+            }
+
+            let repeat = match hold {
+                Some(hold) if hold.direction == direction => {
+                    let wait = match hold.repeats {
+                        0 => self.config.delay,
+                        repeats => self.config.interval_after(repeats),
+                    };
+                    if !wait.elapsed(hold.last, now) {
+                        continue;
+                    }
+
+                    hold.last = wait.advance(hold.last, now);
+                    hold.repeats += 1;
+                    if self
+                        .config
+                        .interval_after(hold.repeats)
+                        .elapsed(hold.last, now)
+                    {
+                        hold.last = now;
+                    }
+                    true
+                }
+                _ => {
+                    *hold = Some(Hold {
+                        direction,
+                        last: now,
+                        repeats: 0,
+                    });
+                    false
+                }
+            };
+
+            ticks.push(RepeatTick {
+                player,
+                direction,
+                repeat,
+            });
+        }
+
+        ticks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::Button;
+    use crate::state::test_states::holding;
+
+    const FRAME_MS: f64 = 1000.0 / 60.0;
+
+    /// Runs `frames` frames at 60 fps and returns the time of every tick.
+    fn tick_times(repeat: &mut AutoRepeat, state: &ControllerState, frames: u32) -> Vec<f64> {
+        (0..frames)
+            .map(|frame| frame as f64 * FRAME_MS)
+            .filter(|now| !repeat.update(state, *now).is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn repeats_after_the_delay_then_every_interval() {
+        let mut repeat = AutoRepeat::new(RepeatConfig::default());
+        let times = tick_times(&mut repeat, &holding(&[Button::Down]), 61);
+
+        // The press, then the first frame at or after 300, 380, 460, ... ms
+        let expected: Vec<f64> = [0.0]
+            .into_iter()
+            .chain((0..9).map(|repeat| 300.0 + 80.0 * repeat as f64))
+            .map(|due: f64| (due / FRAME_MS - 1e-9).ceil() * FRAME_MS)
+            .collect();
+        assert_eq!(times.len(), expected.len());
+        for (time, expected) in times.iter().zip(expected) {
+            assert!((time - expected).abs() < 1e-6, "{time} != {expected}");
+        }
+    }
+
+    #[test]
+    fn repeats_a_diagonal_once_per_interval() {
+        let mut repeat = AutoRepeat::new(RepeatConfig::default());
+        let state = holding(&[Button::Up, Button::Right]);
+
+        let ticks = repeat.update(&state, 0.0);
+        assert_eq!(
+            ticks,
+            vec![RepeatTick {
+                player: Player::One,
+                direction: Direction::UpRight,
+                repeat: false,
+            }]
+        );
+
+        let ticks = repeat.update(&state, 300.0);
+        assert_eq!(ticks.len(), 1);
+        assert_eq!(ticks[0].direction, Direction::UpRight);
+        assert!(ticks[0].repeat);
+    }
+
+    #[test]
+    fn changing_direction_is_a_new_press() {
+        let mut repeat = AutoRepeat::new(RepeatConfig::default());
+        repeat.update(&holding(&[Button::Up]), 0.0);
+
+        let ticks = repeat.update(&holding(&[Button::Up, Button::Left]), 10.0);
+        assert_eq!(ticks.len(), 1);
+        assert_eq!(ticks[0].direction, Direction::UpLeft);
+        assert!(!ticks[0].repeat);
+    }
+
+    #[test]
+    fn a_long_frame_gives_one_tick_and_keeps_the_interval() {
+        let mut repeat = AutoRepeat::new(RepeatConfig::default());
+        let state = holding(&[Button::Left]);
+        repeat.update(&state, 0.0);
+
+        assert_eq!(repeat.update(&state, 2000.0).len(), 1);
+        assert!(repeat.update(&state, 2070.0).is_empty());
+        assert_eq!(repeat.update(&state, 2080.0).len(), 1);
+    }
+
+    #[test]
+    fn releasing_stops_repeats() {
+        let mut repeat = AutoRepeat::new(RepeatConfig::default());
+        repeat.update(&holding(&[Button::Right]), 0.0);
+        assert!(repeat.update(&ControllerState::default(), 400.0).is_empty());
+
+        let ticks = repeat.update(&holding(&[Button::Right]), 410.0);
+        assert!(!ticks[0].repeat);
+    }
+}
@@ -0,0 +1,403 @@
+//! Who is playing: the title screen, attract mode, and one- or two-player games started with the
+//! player-select buttons.
+//!
+//! The player-select buttons belong to one player each: `SystemButton::OnePlayer` to
+//! `Player::One` and `SystemButton::TwoPlayer` to `Player::Two`. Pressing one on the title
+//! screen starts a game (the two-player button starts a game for both), and pressing the other
+//! player's button during a one-player game lets them join in.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::events::Input;
+use crate::state::{Button, ControllerState, Player, SystemButton};
+use crate::timing::{Clock, Span, Tick};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SessionState {
+    /// On the title screen, waiting for a player to start.
+    #[default]
+    Idle,
+    /// Nobody has touched the controls for a while; the game shows its demo loop.
+    Attract,
+    /// A game with only this player.
+    OnePlayer(Player),
+    TwoPlayer,
+}
+
+impl SessionState {
+    pub fn is_playing(&self, player: Player) -> bool {
+        match self {
+            SessionState::OnePlayer(playing) => *playing == player,
+            SessionState::TwoPlayer => true,
+            SessionState::Idle | SessionState::Attract => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TransitionCause {
+    /// A player-select button started a game.
+    Start,
+    /// A player-select button added a player to a one-player game.
+    JoinIn,
+    /// The game removed a player with [`Session::drop_out`].
+    DropOut,
+    /// The game ended with [`Session::end`].
+    End,
+    /// Nobody (or, in a game, one of the players) used the controls for too long.
+    Timeout,
+    /// Someone touched the controls during attract mode.
+    Wake,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Transition {
+    pub from: SessionState,
+    pub to: SessionState,
+    pub cause: TransitionCause,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SessionConfig {
+    /// Time without input on the title screen before attract mode starts. `None` never starts it.
+    pub attract_after: Option<Span>,
+    /// Time without input from a player in a game before they are dropped. The game goes back to
+    /// attract mode once nobody is left. `None` never drops anyone.
+    pub inactivity_timeout: Option<Span>,
+    /// Whether a second player can join a one-player game.
+    pub join_in: bool,
+}
+
+/// Attract mode after 30 s on the title screen, players dropped after 60 s without input.
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            attract_after: Some(Span::Millis(30_000.0)),
+            inactivity_timeout: Some(Span::Millis(60_000.0)),
+            join_in: true,
+        }
+    }
+}
+
+/// Tracks the session state from the player-select buttons and player activity. Call
+/// [`Session::update`] once per frame.
+pub struct Session {
+    config: SessionConfig,
+    state: SessionState,
+    previous: ControllerState,
+    clock: Clock,
+    /// The moment of the last update, `None` before the first.
+    now: Option<Tick>,
+    /// Last input from anyone, for attract mode.
+    last_input: Tick,
+    /// Last input from each player, for dropping inactive players.
+    last_player_input: [Tick; 2],
+}
+
+impl Session {
+    pub fn new(config: SessionConfig) -> Self {
+        Self {
+            config,
+            state: SessionState::Idle,
+            previous: ControllerState::default(),
+            clock: Clock::default(),
+            now: None,
+            last_input: Tick::default(),
+            last_player_input: Default::default(),
+        }
+    }
+
+    pub fn config(&self) -> &SessionConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: SessionConfig) {
+        self.config = config;
+    }
+
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    pub fn is_playing(&self, player: Player) -> bool {
+        self.state.is_playing(player)
+    }
+
+    /// Feeds one frame of input and returns the transitions it caused, in order.
+    pub fn update(&mut self, state: &ControllerState, now_ms: f64) -> Vec<Transition> {
+        let now = self.clock.tick(now_ms);
+        if self.now.is_none() {
+            // Count idle time from the first update, however late it comes
+            self.last_input = now;
+            self.last_player_input = [now; 2];
+        }
+        self.now = Some(now);
+        let previous = std::mem::replace(&mut self.previous, *state);
+        let mut transitions = Vec::new();
+
+        let any_input = state.iter_pressed().next().is_some();
+        if any_input {
+            self.last_input = now;
+        }
+        for player in Player::ALL {
+            let held = Button::ALL
+                .into_iter()
+                .any(|button| state.is_pressed(player, button));
+            if held || state.is_system_pressed(SystemButton::for_player(player)) {
+                self.last_player_input[player.index()] = now;
+            }
+        }
+
+        let just_pressed = |player: Player| {
+            let input = Input::System(SystemButton::for_player(player));
+            state.is_input_pressed(input) && !previous.is_input_pressed(input)
+        };
+
+        match self.state {
+            SessionState::Idle | SessionState::Attract => {
+                if just_pressed(Player::Two) {
+                    self.start(SessionState::TwoPlayer, &mut transitions);
+                } else if just_pressed(Player::One) {
+                    self.start(SessionState::OnePlayer(Player::One), &mut transitions);
+                } else if self.state == SessionState::Attract && any_input {
+                    self.transition(SessionState::Idle, TransitionCause::Wake, &mut transitions);
+                } else if self.state == SessionState::Idle
+                    && self
+                        .config
+                        .attract_after
+                        .is_some_and(|span| span.elapsed(self.last_input, now))
+                {
+                    self.transition(
+                        SessionState::Attract,
+                        TransitionCause::Timeout,
+                        &mut transitions,
+                    );
+                }
+            }
+            SessionState::OnePlayer(player) => {
+                let other = player.other();
+                if self.config.join_in && just_pressed(other) {
+                    self.last_player_input[other.index()] = now;
+                    self.transition(
+                        SessionState::TwoPlayer,
+                        TransitionCause::JoinIn,
+                        &mut transitions,
+                    );
+                } else if self.timed_out(player) {
+                    self.transition(
+                        SessionState::Attract,
+                        TransitionCause::Timeout,
+                        &mut transitions,
+                    );
+                }
+            }
+            SessionState::TwoPlayer => {
+                let timed_out: Vec<Player> = Player::ALL
+                    .into_iter()
+                    .filter(|player| self.timed_out(*player))
+                    .collect();
+
+                match timed_out.as_slice() {
+                    [] => {}
+                    [player] => self.transition(
+                        SessionState::OnePlayer(player.other()),
+                        TransitionCause::Timeout,
+                        &mut transitions,
+                    ),
+                    _ => self.transition(
+                        SessionState::Attract,
+                        TransitionCause::Timeout,
+                        &mut transitions,
+                    ),
+                }
+            }
+        }
+
+        transitions
+    }
+
+    /// Ends the game (e.g. on game over) and goes back to the title screen.
+    pub fn end(&mut self) -> Option<Transition> {
+        match self.state {
+            SessionState::Idle | SessionState::Attract => None,
+            _ => {
+                self.last_input = self.now();
+                Some(self.set_state(SessionState::Idle, TransitionCause::End))
+            }
+        }
+    }
+
+    /// Removes a player from the game, for example when they run out of lives. Dropping the last
+    /// player ends the game.
+    pub fn drop_out(&mut self, player: Player) -> Option<Transition> {
+        let next = match self.state {
+            SessionState::TwoPlayer => SessionState::OnePlayer(player.other()),
+            SessionState::OnePlayer(playing) if playing == player => {
+                self.last_input = self.now();
+                SessionState::Idle
+            }
+            _ => return None,
+        };
+
+        Some(self.set_state(next, TransitionCause::DropOut))
+    }
+
+    fn start(&mut self, next: SessionState, transitions: &mut Vec<Transition>) {
+        self.last_player_input = [self.now(); 2];
+        self.transition(next, TransitionCause::Start, transitions);
+    }
+
+    fn now(&self) -> Tick {
+        self.now.unwrap_or_default()
+    }
+
+    fn timed_out(&self, player: Player) -> bool {
+        self.config
+            .inactivity_timeout
+            .is_some_and(|span| span.elapsed(self.last_player_input[player.index()], self.now()))
+    }
+
+    fn transition(
+        &mut self,
+        next: SessionState,
+        cause: TransitionCause,
+        transitions: &mut Vec<Transition>,
+    ) {
+        if next == SessionState::Attract || next == SessionState::Idle {
+            self.last_input = self.now();
+        }
+        transitions.push(self.set_state(next, cause));
+    }
+
+    fn set_state(&mut self, next: SessionState, cause: TransitionCause) -> Transition {
+        let transition = Transition {
+            from: self.state,
+            to: next,
+            cause,
+        };
+        self.state = next;
+        transition
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::test_states::holding_inputs;
+
+    fn session() -> Session {
+        Session::new(SessionConfig {
+            attract_after: Some(Span::Millis(1000.0)),
+            inactivity_timeout: Some(Span::Millis(5000.0)),
+            join_in: true,
+        })
+    }
+
+    fn causes(transitions: Vec<Transition>) -> Vec<(SessionState, TransitionCause)> {
+        transitions
+            .into_iter()
+            .map(|transition| (transition.to, transition.cause))
+            .collect()
+    }
+
+    const ONE_PLAYER: Input = Input::System(SystemButton::OnePlayer);
+    const TWO_PLAYER: Input = Input::System(SystemButton::TwoPlayer);
+    const P1_A: Input = Input::Button(Player::One, Button::A);
+
+    #[test]
+    fn a_late_first_update_does_not_start_attract_mode() {
+        let mut session = session();
+        assert!(
+            session
+                .update(&ControllerState::default(), 50_000.0)
+                .is_empty()
+        );
+        assert_eq!(session.state(), SessionState::Idle);
+
+        assert!(
+            session
+                .update(&ControllerState::default(), 50_999.0)
+                .is_empty()
+        );
+        assert_eq!(
+            causes(session.update(&ControllerState::default(), 51_000.0)),
+            [(SessionState::Attract, TransitionCause::Timeout)]
+        );
+    }
+
+    #[test]
+    fn input_wakes_attract_mode() {
+        let mut session = session();
+        session.update(&ControllerState::default(), 0.0);
+        session.update(&ControllerState::default(), 1000.0);
+        assert_eq!(session.state(), SessionState::Attract);
+
+        assert_eq!(
+            causes(session.update(&holding_inputs(&[P1_A]), 1100.0)),
+            [(SessionState::Idle, TransitionCause::Wake)]
+        );
+    }
+
+    #[test]
+    fn player_select_buttons_start_games() {
+        let mut session = session();
+        assert_eq!(
+            causes(session.update(&holding_inputs(&[ONE_PLAYER]), 0.0)),
+            [(SessionState::OnePlayer(Player::One), TransitionCause::Start)]
+        );
+
+        let mut session = self::session();
+        assert_eq!(
+            causes(session.update(&holding_inputs(&[TWO_PLAYER]), 0.0)),
+            [(SessionState::TwoPlayer, TransitionCause::Start)]
+        );
+    }
+
+    #[test]
+    fn the_other_player_joins_in() {
+        let mut session = session();
+        session.update(&holding_inputs(&[ONE_PLAYER]), 0.0);
+        session.update(&ControllerState::default(), 10.0);
+
+        assert_eq!(
+            causes(session.update(&holding_inputs(&[TWO_PLAYER]), 20.0)),
+            [(SessionState::TwoPlayer, TransitionCause::JoinIn)]
+        );
+    }
+
+    #[test]
+    fn inactive_players_are_dropped() {
+        let mut session = session();
+        session.update(&holding_inputs(&[TWO_PLAYER]), 0.0);
+
+        assert!(session.update(&holding_inputs(&[P1_A]), 4000.0).is_empty());
+        assert_eq!(
+            causes(session.update(&holding_inputs(&[P1_A]), 5000.0)),
+            [(
+                SessionState::OnePlayer(Player::One),
+                TransitionCause::Timeout
+            )]
+        );
+        assert_eq!(
+            causes(session.update(&ControllerState::default(), 10_000.0)),
+            [(SessionState::Attract, TransitionCause::Timeout)]
+        );
+    }
+
+    #[test]
+    fn dropping_the_last_player_ends_the_game() {
+        let mut session = session();
+        session.update(&holding_inputs(&[ONE_PLAYER]), 0.0);
+
+        assert_eq!(session.drop_out(Player::Two), None);
+        assert_eq!(
+            session.drop_out(Player::One).map(|t| (t.to, t.cause)),
+            Some((SessionState::Idle, TransitionCause::DropOut))
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! Seating players on either side of the cabinet.
+//!
+//! Games read `player1_*` for the first player and `player2_*` for the second. [`PlayerSlots`]
+//! rewrites each frame so that those fields come from whichever physical side each player is
+//! seated at. A lone player who walks up to the right-hand side can then play as player one
+//! without the game changing its reads.
+//!
+//! Each side's player-select button moves with the side, so a player seated as player one always
+//! starts with `system_one_player`. Extra buttons are passed through unchanged: which side each
+//! one belongs to is only known from the controller's [`Layout`](crate::layout::Layout), and the
+//! two sides need not have the same extras to swap.
+
+use crate::events::Input;
+use crate::state::{ControllerState, Player, SystemButton};
+
+/// Maps the physical sides of the cabinet to the players the game sees. Call
+/// [`PlayerSlots::update`] once per frame and give the game its result.
+pub struct PlayerSlots {
+    /// Physical side of each logical player.
+    sides: [Player; 2],
+    auto_assign: bool,
+    assigned: bool,
+    previous: ControllerState,
+}
+
+impl PlayerSlots {
+    /// Seats player one on the left and player two on the right.
+    pub fn new() -> Self {
+        Self {
+            sides: Player::ALL,
+            auto_assign: false,
+            assigned: false,
+            previous: ControllerState::default(),
+        }
+    }
+
+    /// Seats player one at whichever side presses its player-select button first, until the next
+    /// [`PlayerSlots::reset`].
+    pub fn auto_assign(mut self, auto_assign: bool) -> Self {
+        self.auto_assign = auto_assign;
+        self
+    }
+
+    /// The physical side `player` is seated at.
+    pub fn side_of(&self, player: Player) -> Player {
+        self.sides[player.index()]
+    }
+
+    /// The player seated at the physical `side`.
+    pub fn player_at(&self, side: Player) -> Player {
+        if self.sides[Player::One.index()] == side {
+            Player::One
+        } else {
+            Player::Two
+        }
+    }
+
+    /// Seats `player` at `side`, and the other player at the other side.
+    pub fn assign(&mut self, player: Player, side: Player) {
+        self.sides[player.index()] = side;
+        self.sides[player.other().index()] = side.other();
+        self.assigned = true;
+    }
+
+    /// Swaps the sides of the two players.
+    pub fn swap(&mut self) {
+        self.sides.reverse();
+        self.assigned = true;
+    }
+
+    /// Goes back to player one on the left and, with auto-assign, waits for the next start.
+    pub fn reset(&mut self) {
+        self.sides = Player::ALL;
+        self.assigned = false;
+    }
+
+    /// Feeds one frame of input and returns it with the players in their seats.
+    pub fn update(&mut self, state: &ControllerState) -> ControllerState {
+        let previous = std::mem::replace(&mut self.previous, *state);
+
+        if self.auto_assign && !self.assigned {
+            let started = Player::ALL.into_iter().find(|side| {
+                let button = SystemButton::for_player(*side);
+                state.is_system_pressed(button) && !previous.is_system_pressed(button)
+            });
+            if let Some(side) = started {
+                self.assign(Player::One, side);
+            }
+        }
+
+        let mut seated = *state;
+        for player in Player::ALL {
+            let side = self.side_of(player);
+            seated.set_player(player, state.player(side));
+            seated.set_input_pressed(
+                Input::System(SystemButton::for_player(player)),
+                state.is_system_pressed(SystemButton::for_player(side)),
+            );
+        }
+
+        seated
+    }
+}
+
+impl Default for PlayerSlots {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::Button;
+    use crate::state::test_states::holding_inputs;
+
+    const P1_A: Input = Input::Button(Player::One, Button::A);
+    const P2_A: Input = Input::Button(Player::Two, Button::A);
+    const ONE_PLAYER: Input = Input::System(SystemButton::OnePlayer);
+    const TWO_PLAYER: Input = Input::System(SystemButton::TwoPlayer);
+
+    #[test]
+    fn seats_are_passed_through_by_default() {
+        let mut slots = PlayerSlots::new();
+        let state = holding_inputs(&[P2_A, TWO_PLAYER]);
+
+        assert_eq!(slots.update(&state), state);
+    }
+
+    #[test]
+    fn starting_on_the_right_seats_player_one_there() {
+        let mut slots = PlayerSlots::new().auto_assign(true);
+        let seated = slots.update(&holding_inputs(&[TWO_PLAYER]));
+
+        assert_eq!(slots.side_of(Player::One), Player::Two);
+        assert_eq!(slots.player_at(Player::One), Player::Two);
+        assert_eq!(seated, holding_inputs(&[ONE_PLAYER]));
+        assert_eq!(
+            slots.update(&holding_inputs(&[P2_A])),
+            holding_inputs(&[P1_A])
+        );
+    }
+
+    #[test]
+    fn seats_stay_until_reset() {
+        let mut slots = PlayerSlots::new().auto_assign(true);
+        slots.update(&holding_inputs(&[TWO_PLAYER]));
+        slots.update(&ControllerState::default());
+        slots.update(&holding_inputs(&[ONE_PLAYER]));
+        assert_eq!(slots.side_of(Player::One), Player::Two);
+
+        slots.reset();
+        assert_eq!(slots.side_of(Player::One), Player::One);
+        slots.update(&ControllerState::default());
+        slots.update(&holding_inputs(&[ONE_PLAYER]));
+        assert_eq!(slots.side_of(Player::One), Player::One);
+    }
+
+    #[test]
+    fn swap_exchanges_both_players() {
+        let mut slots = PlayerSlots::new();
+        slots.swap();
+
+        assert_eq!(
+            slots.update(&holding_inputs(&[P1_A, ONE_PLAYER])),
+            holding_inputs(&[P2_A, TWO_PLAYER])
+        );
+    }
+
+    #[test]
+    fn extra_buttons_pass_through() {
+        let mut slots = PlayerSlots::new();
+        slots.swap();
+        let mut state = ControllerState::default();
+        state.set_extra_pressed(3, true);
+
+        assert!(slots.update(&state).is_extra_pressed(3));
+    }
+}
@@ -0,0 +1,229 @@
+//! SOCD (simultaneous opposing cardinal directions) cleaning.
+//!
+//! The cabinet reports each d-pad direction independently, so left and right (or up and down)
+//! can be held at the same time. [`SocdCleaner`] resolves those conflicts the same way for every
+//! game and can optionally restrict the stick to four directions.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::state::{ControllerState, Direction, Player, PlayerState};
+
+/// How to resolve opposite directions held together.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SocdMode {
+    /// Opposite directions cancel out.
+    #[default]
+    Neutral,
+    /// The direction pressed most recently wins.
+    LastInputWins,
+    /// The direction held the longest wins.
+    FirstInputWins,
+    /// Up wins over down; left and right cancel out.
+    UpPriority,
+}
+
+/// Frame each d-pad direction was pressed on, `None` while released.
+#[derive(Clone, Copy, Debug, Default)]
+struct Held {
+    up: Option<u64>,
+    down: Option<u64>,
+    left: Option<u64>,
+    right: Option<u64>,
+}
+
+/// Resolves one axis to -1, 0 or 1 given when its negative and positive directions were pressed.
+fn resolve(negative: Option<u64>, positive: Option<u64>, mode: SocdMode, vertical: bool) -> i8 {
+    match (negative, positive) {
+        (None, None) => 0,
+        (Some(_), None) => -1,
+        (None, Some(_)) => 1,
+        (Some(negative), Some(positive)) => match mode {
+            SocdMode::Neutral => 0,
+            SocdMode::LastInputWins => positive.cmp(&negative) as i8,
+            SocdMode::FirstInputWins => negative.cmp(&positive) as i8,
+            SocdMode::UpPriority if vertical => -1,
+            SocdMode::UpPriority => 0,
+        },
+    }
+}
+
+/// Cleans both players' d-pads. Call [`SocdCleaner::update`] once per frame.
+pub struct SocdCleaner {
+    mode: SocdMode,
+    four_way: bool,
+    held: [Held; 2],
+    directions: [Direction; 2],
+    frame: u64,
+}
+
+impl SocdCleaner {
+    pub fn new(mode: SocdMode) -> Self {
+        Self {
+            mode,
+            four_way: false,
+            held: Default::default(),
+            directions: Default::default(),
+            frame: 0,
+        }
+    }
+
+    /// Only allows up, down, left and right. On a diagonal, the axis pressed last wins so that
+    /// turning corners in a maze works without releasing the old direction first.
+    pub fn four_way(mut self, four_way: bool) -> Self {
+        self.four_way = four_way;
+        self
+    }
+
+    pub fn set_mode(&mut self, mode: SocdMode) {
+        self.mode = mode;
+    }
+
+    /// Feeds one frame of input and returns it with each player's d-pad cleaned.
+    pub fn update(&mut self, state: &ControllerState) -> ControllerState {
+        self.frame += 1;
+        let mut cleaned = *state;
+
+        for player in Player::ALL {
+            let current = state.player(player);
+            let held = &mut self.held[player.index()];
+            let frame = self.frame;
+            let track = |since: &mut Option<u64>, pressed: bool| {
+                *since = match (*since, pressed) {
+                    (_, false) => None,
+                    (None, true) => Some(frame),
+                    (Some(since), true) => Some(since),
+                };
+            };
+
+            track(&mut held.up, current.up);
+            track(&mut held.down, current.down);
+            track(&mut held.left, current.left);
+            track(&mut held.right, current.right);
+
+            let mut x = resolve(held.left, held.right, self.mode, false);
+            let mut y = resolve(held.up, held.down, self.mode, true);
+
+            if self.four_way && x != 0 && y != 0 {
+                let horizontal = if x < 0 { held.left } else { held.right };
+                let vertical = if y < 0 { held.up } else { held.down };
+                if vertical > horizontal {
+                    x = 0;
+                } else {
+                    y = 0;
+                }
+            }
+
+            let direction = Direction::from_dpad(x, y);
+            self.directions[player.index()] = direction;
+
+            cleaned.set_player(
+                player,
+                PlayerState {
+                    up: y < 0,
+                    down: y > 0,
+                    left: x < 0,
+                    right: x > 0,
+                    ..current
+                },
+            );
+        }
+
+        cleaned
+    }
+
+    /// The player's cleaned direction as of the last update.
+    pub fn direction(&self, player: Player) -> Direction {
+        self.directions[player.index()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::Button;
+    use crate::state::test_states::holding;
+
+    /// Holds `first`, then adds `second`, and returns the cleaned direction.
+    fn press_in_order(cleaner: &mut SocdCleaner, first: Button, second: Button) -> Direction {
+        cleaner.update(&holding(&[first]));
+        cleaner.update(&holding(&[first, second]));
+        cleaner.direction(Player::One)
+    }
+
+    #[test]
+    fn neutral_cancels_opposites() {
+        let mut cleaner = SocdCleaner::new(SocdMode::Neutral);
+        assert_eq!(
+            press_in_order(&mut cleaner, Button::Left, Button::Right),
+            Direction::Neutral
+        );
+
+        let cleaned = cleaner.update(&holding(&[Button::Left, Button::Right, Button::Up]));
+        assert!(!cleaned.player1_left && !cleaned.player1_right && cleaned.player1_up);
+    }
+
+    #[test]
+    fn last_input_wins() {
+        let mut cleaner = SocdCleaner::new(SocdMode::LastInputWins);
+        assert_eq!(
+            press_in_order(&mut cleaner, Button::Left, Button::Right),
+            Direction::Right
+        );
+
+        // Releasing the newer direction gives the held one back
+        cleaner.update(&holding(&[Button::Left]));
+        assert_eq!(cleaner.direction(Player::One), Direction::Left);
+    }
+
+    #[test]
+    fn first_input_wins() {
+        let mut cleaner = SocdCleaner::new(SocdMode::FirstInputWins);
+        assert_eq!(
+            press_in_order(&mut cleaner, Button::Down, Button::Up),
+            Direction::Down
+        );
+    }
+
+    #[test]
+    fn up_priority_only_applies_vertically() {
+        let mut cleaner = SocdCleaner::new(SocdMode::UpPriority);
+        assert_eq!(
+            press_in_order(&mut cleaner, Button::Up, Button::Down),
+            Direction::Up
+        );
+
+        let mut cleaner = SocdCleaner::new(SocdMode::UpPriority);
+        assert_eq!(
+            press_in_order(&mut cleaner, Button::Right, Button::Left),
+            Direction::Neutral
+        );
+    }
+
+    #[test]
+    fn four_way_keeps_the_newest_axis() {
+        let mut cleaner = SocdCleaner::new(SocdMode::Neutral).four_way(true);
+        assert_eq!(
+            press_in_order(&mut cleaner, Button::Up, Button::Right),
+            Direction::Right
+        );
+
+        let mut cleaner = SocdCleaner::new(SocdMode::Neutral).four_way(true);
+        assert_eq!(
+            press_in_order(&mut cleaner, Button::Right, Button::Up),
+            Direction::Up
+        );
+    }
+
+    #[test]
+    fn players_are_cleaned_independently() {
+        let mut cleaner = SocdCleaner::new(SocdMode::Neutral);
+        let mut state = holding(&[Button::Left, Button::Right]);
+        state.set_pressed(Player::Two, Button::Left, true);
+
+        cleaner.update(&state);
+        assert_eq!(cleaner.direction(Player::One), Direction::Neutral);
+        assert_eq!(cleaner.direction(Player::Two), Direction::Left);
+    }
+}
@@ -1,4 +1,33 @@
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+use core::fmt;
+
+pub use rcade_sdk::player::Player;
+use rcade_sdk::replay::Frame;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::events::{Input, InputEvent};
+use crate::layout::Layout;
+
+// Bit positions in the packed representation (must match worker.js)
+const CONNECTED: u32 = 0;
+const SYSTEM_ONE_PLAYER: u32 = 1;
+const SYSTEM_TWO_PLAYER: u32 = 2;
+const PLAYER1_UP: u32 = 3;
+const PLAYER1_DOWN: u32 = 4;
+const PLAYER1_LEFT: u32 = 5;
+const PLAYER1_RIGHT: u32 = 6;
+const PLAYER1_A: u32 = 7;
+const PLAYER1_B: u32 = 8;
+const PLAYER2_UP: u32 = 9;
+const PLAYER2_DOWN: u32 = 10;
+const PLAYER2_LEFT: u32 = 11;
+const PLAYER2_RIGHT: u32 = 12;
+const PLAYER2_A: u32 = 13;
+const PLAYER2_B: u32 = 14;
+const FIRST_EXTRA: u32 = 15;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ControllerState {
     pub connected: bool,
     pub system_one_player: bool,
@@ -15,4 +44,677 @@ pub struct ControllerState {
     pub player2_right: bool,
     pub player2_a: bool,
     pub player2_b: bool,
+    /// Buttons beyond the standard layout, bit `i` being the button at index `i` in the
+    /// controller's [`Layout`]. Only the low [`Layout::MAX_EXTRA_BUTTONS`] bits are used.
+    pub extra: u32,
+}
+
+impl ControllerState {
+    /// Unpacks a state from its bitmask form, as produced by [`ControllerState::to_bits`].
+    ///
+    /// Bits above the standard inputs are extra buttons.
+    pub fn from_bits(bits: u32) -> Self {
+        let bit = |index: u32| bits & (1 << index) != 0;
+
+        ControllerState {
+            connected: bit(CONNECTED),
+            system_one_player: bit(SYSTEM_ONE_PLAYER),
+            system_two_player: bit(SYSTEM_TWO_PLAYER),
+            player1_up: bit(PLAYER1_UP),
+            player1_down: bit(PLAYER1_DOWN),
+            player1_left: bit(PLAYER1_LEFT),
+            player1_right: bit(PLAYER1_RIGHT),
+            player1_a: bit(PLAYER1_A),
+            player1_b: bit(PLAYER1_B),
+            player2_up: bit(PLAYER2_UP),
+            player2_down: bit(PLAYER2_DOWN),
+            player2_left: bit(PLAYER2_LEFT),
+            player2_right: bit(PLAYER2_RIGHT),
+            player2_a: bit(PLAYER2_A),
+            player2_b: bit(PLAYER2_B),
+            extra: bits >> FIRST_EXTRA,
+        }
+    }
+
+    /// Packs the state into a bitmask, one bit per field. Useful for compact serialization.
+    pub fn to_bits(&self) -> u32 {
+        let bit = |set: bool, index: u32| (set as u32) << index;
+
+        bit(self.connected, CONNECTED)
+            | bit(self.system_one_player, SYSTEM_ONE_PLAYER)
+            | bit(self.system_two_player, SYSTEM_TWO_PLAYER)
+            | bit(self.player1_up, PLAYER1_UP)
+            | bit(self.player1_down, PLAYER1_DOWN)
+            | bit(self.player1_left, PLAYER1_LEFT)
+            | bit(self.player1_right, PLAYER1_RIGHT)
+            | bit(self.player1_a, PLAYER1_A)
+            | bit(self.player1_b, PLAYER1_B)
+            | bit(self.player2_up, PLAYER2_UP)
+            | bit(self.player2_down, PLAYER2_DOWN)
+            | bit(self.player2_left, PLAYER2_LEFT)
+            | bit(self.player2_right, PLAYER2_RIGHT)
+            | bit(self.player2_a, PLAYER2_A)
+            | bit(self.player2_b, PLAYER2_B)
+            | self.extra << FIRST_EXTRA
+    }
+
+    /// The buttons of one player, without the `player1_`/`player2_` prefixes.
+    pub fn player(&self, player: Player) -> PlayerState {
+        match player {
+            Player::One => PlayerState {
+                up: self.player1_up,
+                down: self.player1_down,
+                left: self.player1_left,
+                right: self.player1_right,
+                a: self.player1_a,
+                b: self.player1_b,
+            },
+            Player::Two => PlayerState {
+                up: self.player2_up,
+                down: self.player2_down,
+                left: self.player2_left,
+                right: self.player2_right,
+                a: self.player2_a,
+                b: self.player2_b,
+            },
+        }
+    }
+
+    /// Replaces all of one player's buttons.
+    pub fn set_player(&mut self, player: Player, state: PlayerState) {
+        let PlayerState {
+            up,
+            down,
+            left,
+            right,
+            a,
+            b,
+        } = state;
+
+        match player {
+            Player::One => {
+                self.player1_up = up;
+                self.player1_down = down;
+                self.player1_left = left;
+                self.player1_right = right;
+                self.player1_a = a;
+                self.player1_b = b;
+            }
+            Player::Two => {
+                self.player2_up = up;
+                self.player2_down = down;
+                self.player2_left = left;
+                self.player2_right = right;
+                self.player2_a = a;
+                self.player2_b = b;
+            }
+        }
+    }
+
+    pub fn is_pressed(&self, player: Player, button: Button) -> bool {
+        self.player(player).is_pressed(button)
+    }
+
+    pub fn set_pressed(&mut self, player: Player, button: Button, pressed: bool) {
+        let mut state = self.player(player);
+        state.set_pressed(button, pressed);
+        self.set_player(player, state);
+    }
+
+    pub fn is_system_pressed(&self, button: SystemButton) -> bool {
+        match button {
+            SystemButton::OnePlayer => self.system_one_player,
+            SystemButton::TwoPlayer => self.system_two_player,
+        }
+    }
+
+    pub fn is_input_pressed(&self, input: Input) -> bool {
+        match input {
+            Input::Button(player, button) => self.is_pressed(player, button),
+            Input::System(button) => self.is_system_pressed(button),
+        }
+    }
+
+    pub fn set_input_pressed(&mut self, input: Input, pressed: bool) {
+        match input {
+            Input::Button(player, button) => self.set_pressed(player, button, pressed),
+            Input::System(SystemButton::OnePlayer) => self.system_one_player = pressed,
+            Input::System(SystemButton::TwoPlayer) => self.system_two_player = pressed,
+        }
+    }
+
+    /// Every extra button as a bitmask, bit `i` being the button at index `i` in the controller's
+    /// [`Layout`].
+    pub fn extra_bits(&self) -> u32 {
+        self.extra
+    }
+
+    /// Whether the extra button at `index` in the controller's [`Layout`] is held down.
+    pub fn is_extra_pressed(&self, index: usize) -> bool {
+        index < Layout::MAX_EXTRA_BUTTONS && self.extra & (1 << index) != 0
+    }
+
+    pub fn set_extra_pressed(&mut self, index: usize, pressed: bool) {
+        if index >= Layout::MAX_EXTRA_BUTTONS {
+            return;
+        }
+
+        if pressed {
+            self.extra |= 1 << index;
+        } else {
+            self.extra &= !(1 << index);
+        }
+    }
+
+    /// Every standard input currently held down, system buttons first.
+    ///
+    /// Extra buttons are left out: they have no [`Input`] and only mean something together with
+    /// the controller's [`Layout`]. Read them with [`ControllerState::is_extra_pressed`].
+    pub fn iter_pressed(&self) -> impl Iterator<Item = Input> + use<> {
+        let state = *self;
+
+        Input::all().filter(move |input| state.is_input_pressed(*input))
+    }
+
+    /// The standard inputs that were pressed or released going from `previous` to this state,
+    /// system buttons first.
+    ///
+    /// Like [`ControllerState::iter_pressed`], this leaves out extra buttons. The extras that
+    /// changed are the set bits of `self.extra_bits() ^ previous.extra_bits()`.
+    pub fn diff(&self, previous: &ControllerState) -> impl Iterator<Item = InputEvent> + use<> {
+        let (state, previous) = (*self, *previous);
+
+        Input::all().filter_map(move |input| {
+            let pressed = state.is_input_pressed(input);
+            (pressed != previous.is_input_pressed(input)).then_some(InputEvent { input, pressed })
+        })
+    }
+}
+
+/// A one-line summary for debugging, e.g. `connected | P1: up a | P2: - | system: 1P`. Held
+/// extra buttons are appended as a bitmask, e.g. ` | extra: 0x5`.
+impl fmt::Display for ControllerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.connected {
+            "connected"
+        } else {
+            "disconnected"
+        })?;
+
+        for player in Player::ALL {
+            write!(f, " | {player}:")?;
+            let mut any = false;
+            for button in Button::ALL
+                .into_iter()
+                .filter(|b| self.is_pressed(player, *b))
+            {
+                write!(f, " {button}")?;
+                any = true;
+            }
+            if !any {
+                f.write_str(" -")?;
+            }
+        }
+
+        f.write_str(" | system:")?;
+        let mut any = false;
+        for button in SystemButton::ALL
+            .into_iter()
+            .filter(|b| self.is_system_pressed(*b))
+        {
+            write!(f, " {button}")?;
+            any = true;
+        }
+        if !any {
+            f.write_str(" -")?;
+        }
+
+        if self.extra != 0 {
+            write!(f, " | extra: {:#x}", self.extra)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Recorded as the packed bitmask from [`ControllerState::to_bits`].
+impl Frame for ControllerState {
+    const SIZE: usize = 4;
+
+    fn encode(&self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_bits().to_le_bytes());
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        ControllerState::from_bits(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+/// The buttons of a single player, see [`ControllerState::player`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PlayerState {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub a: bool,
+    pub b: bool,
+}
+
+impl PlayerState {
+    pub fn is_pressed(&self, button: Button) -> bool {
+        match button {
+            Button::Up => self.up,
+            Button::Down => self.down,
+            Button::Left => self.left,
+            Button::Right => self.right,
+            Button::A => self.a,
+            Button::B => self.b,
+        }
+    }
+
+    pub fn set_pressed(&mut self, button: Button, pressed: bool) {
+        match button {
+            Button::Up => self.up = pressed,
+            Button::Down => self.down = pressed,
+            Button::Left => self.left = pressed,
+            Button::Right => self.right = pressed,
+            Button::A => self.a = pressed,
+            Button::B => self.b = pressed,
+        }
+    }
+
+    /// The d-pad as an `(x, y)` vector in screen coordinates: `x` is -1 for left and 1 for right,
+    /// `y` is -1 for up and 1 for down. Opposite directions held together cancel out.
+    pub fn dpad(&self) -> (i8, i8) {
+        let x = self.right as i8 - self.left as i8;
+        let y = self.down as i8 - self.up as i8;
+        (x, y)
+    }
+
+    pub fn direction(&self) -> Direction {
+        let (x, y) = self.dpad();
+        Direction::from_dpad(x, y)
+    }
+}
+
+/// A button belonging to a single player.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Button {
+    Up,
+    Down,
+    Left,
+    Right,
+    A,
+    B,
+}
+
+impl fmt::Display for Button {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Button::Up => "up",
+            Button::Down => "down",
+            Button::Left => "left",
+            Button::Right => "right",
+            Button::A => "a",
+            Button::B => "b",
+        })
+    }
+}
+
+impl Button {
+    /// Number of buttons each player has.
+    pub const COUNT: usize = 6;
+
+    pub const ALL: [Button; Button::COUNT] = [
+        Button::Up,
+        Button::Down,
+        Button::Left,
+        Button::Right,
+        Button::A,
+        Button::B,
+    ];
+
+    /// Position in [`Button::ALL`], for per-button arrays.
+    pub(crate) fn index(self) -> usize {
+        match self {
+            Button::Up => 0,
+            Button::Down => 1,
+            Button::Left => 2,
+            Button::Right => 3,
+            Button::A => 4,
+            Button::B => 5,
+        }
+    }
+}
+
+/// A cabinet-wide button shared by both players.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SystemButton {
+    OnePlayer,
+    TwoPlayer,
+}
+
+impl fmt::Display for SystemButton {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SystemButton::OnePlayer => "1P",
+            SystemButton::TwoPlayer => "2P",
+        })
+    }
+}
+
+impl SystemButton {
+    pub const ALL: [SystemButton; 2] = [SystemButton::OnePlayer, SystemButton::TwoPlayer];
+
+    /// The player-select button on `player`'s side of the cabinet.
+    pub fn for_player(player: Player) -> SystemButton {
+        match player {
+            Player::One => SystemButton::OnePlayer,
+            Player::Two => SystemButton::TwoPlayer,
+        }
+    }
+}
+
+/// An 8-way joystick direction.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Direction {
+    #[default]
+    Neutral,
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl Direction {
+    /// Converts a d-pad vector (see [`PlayerState::dpad`]) to a direction, using only the signs.
+    pub fn from_dpad(x: i8, y: i8) -> Self {
+        match (x.signum(), y.signum()) {
+            (0, -1) => Direction::Up,
+            (1, -1) => Direction::UpRight,
+            (1, 0) => Direction::Right,
+            (1, 1) => Direction::DownRight,
+            (0, 1) => Direction::Down,
+            (-1, 1) => Direction::DownLeft,
+            (-1, 0) => Direction::Left,
+            (-1, -1) => Direction::UpLeft,
+            _ => Direction::Neutral,
+        }
+    }
+
+    /// The same direction with left and right swapped.
+    pub fn mirrored(self) -> Self {
+        let (x, y) = self.dpad();
+        Direction::from_dpad(-x, y)
+    }
+
+    /// The `(x, y)` d-pad vector for this direction, in screen coordinates.
+    pub fn dpad(self) -> (i8, i8) {
+        match self {
+            Direction::Neutral => (0, 0),
+            Direction::Up => (0, -1),
+            Direction::UpRight => (1, -1),
+            Direction::Right => (1, 0),
+            Direction::DownRight => (1, 1),
+            Direction::Down => (0, 1),
+            Direction::DownLeft => (-1, 1),
+            Direction::Left => (-1, 0),
+            Direction::UpLeft => (-1, -1),
+        }
+    }
+}
+
+/// Controller states for the tests of every module.
+#[cfg(test)]
+pub(crate) mod test_states {
+    use super::*;
+
+    /// Player one holding `buttons`, and nothing else.
+    pub(crate) fn holding(buttons: &[Button]) -> ControllerState {
+        let mut state = ControllerState::default();
+        for button in buttons {
+            state.set_pressed(Player::One, *button, true);
+        }
+        state
+    }
+
+    /// Every one of `inputs` held, and nothing else.
+    pub(crate) fn holding_inputs(inputs: &[Input]) -> ControllerState {
+        let mut state = ControllerState::default();
+        for input in inputs {
+            state.set_input_pressed(*input, true);
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bits_round_trip_including_extra_buttons() {
+        for bits in [0, 1, 0b101, 0x7fff, 1 << FIRST_EXTRA, 0xdead_beef, u32::MAX] {
+            assert_eq!(
+                ControllerState::from_bits(bits).to_bits(),
+                bits,
+                "{bits:#x}"
+            );
+        }
+
+        let mut state = ControllerState {
+            connected: true,
+            player2_b: true,
+            ..Default::default()
+        };
+        state.set_extra_pressed(0, true);
+        state.set_extra_pressed(Layout::MAX_EXTRA_BUTTONS - 1, true);
+        assert_eq!(ControllerState::from_bits(state.to_bits()), state);
+    }
+
+    #[test]
+    fn each_standard_input_has_its_own_bit() {
+        let bits: Vec<u32> = Input::all()
+            .map(|input| {
+                let mut state = ControllerState::default();
+                state.set_input_pressed(input, true);
+                state.to_bits()
+            })
+            .collect();
+
+        for (i, bit) in bits.iter().enumerate() {
+            assert_eq!(bit.count_ones(), 1);
+            assert!(bit.trailing_zeros() < FIRST_EXTRA);
+            assert!(!bits[..i].contains(bit));
+        }
+    }
+
+    #[test]
+    fn extra_bits_start_after_the_standard_inputs() {
+        let state = ControllerState::from_bits(0b11 << FIRST_EXTRA);
+        assert_eq!(state.extra_bits(), 0b11);
+        assert!(state.is_extra_pressed(0) && state.is_extra_pressed(1));
+        assert!(!state.is_extra_pressed(2));
+        assert_eq!(state.iter_pressed().count(), 0);
+    }
+
+    #[test]
+    fn extra_buttons_outside_the_layout_are_ignored() {
+        let mut state = ControllerState::default();
+        state.set_extra_pressed(Layout::MAX_EXTRA_BUTTONS, true);
+        assert_eq!(state, ControllerState::default());
+        assert!(!ControllerState::from_bits(u32::MAX).is_extra_pressed(Layout::MAX_EXTRA_BUTTONS));
+    }
+
+    #[test]
+    fn iter_pressed_lists_held_inputs_system_buttons_first() {
+        let mut state = ControllerState::default();
+        state.set_pressed(Player::Two, Button::A, true);
+        state.set_pressed(Player::One, Button::Left, true);
+        state.set_input_pressed(Input::System(SystemButton::TwoPlayer), true);
+        state.set_extra_pressed(3, true);
+
+        assert_eq!(
+            state.iter_pressed().collect::<Vec<_>>(),
+            [
+                Input::System(SystemButton::TwoPlayer),
+                Input::Button(Player::One, Button::Left),
+                Input::Button(Player::Two, Button::A),
+            ]
+        );
+        assert_eq!(ControllerState::default().iter_pressed().count(), 0);
+    }
+
+    fn player_holding(buttons: &[Button]) -> PlayerState {
+        let mut state = PlayerState::default();
+        for button in buttons {
+            state.set_pressed(*button, true);
+        }
+        state
+    }
+
+    #[test]
+    fn dpad_and_direction_cover_all_eight_ways() {
+        use Button::*;
+
+        let cases = [
+            (&[][..], (0, 0), Direction::Neutral),
+            (&[Up], (0, -1), Direction::Up),
+            (&[Up, Right], (1, -1), Direction::UpRight),
+            (&[Right], (1, 0), Direction::Right),
+            (&[Down, Right], (1, 1), Direction::DownRight),
+            (&[Down], (0, 1), Direction::Down),
+            (&[Down, Left], (-1, 1), Direction::DownLeft),
+            (&[Left], (-1, 0), Direction::Left),
+            (&[Up, Left], (-1, -1), Direction::UpLeft),
+            // Face buttons don't move the stick.
+            (&[A, B], (0, 0), Direction::Neutral),
+        ];
+
+        for (buttons, dpad, direction) in cases {
+            let state = player_holding(buttons);
+            assert_eq!(state.dpad(), dpad, "{buttons:?}");
+            assert_eq!(state.direction(), direction, "{buttons:?}");
+            assert_eq!(direction.dpad(), dpad, "{direction:?}");
+        }
+    }
+
+    #[test]
+    fn opposing_inputs_cancel_out() {
+        use Button::*;
+
+        let cases = [
+            (&[Left, Right][..], Direction::Neutral),
+            (&[Up, Down], Direction::Neutral),
+            (&[Up, Down, Left, Right], Direction::Neutral),
+            (&[Left, Right, Up], Direction::Up),
+            (&[Up, Down, Right], Direction::Right),
+        ];
+
+        for (buttons, direction) in cases {
+            assert_eq!(
+                player_holding(buttons).direction(),
+                direction,
+                "{buttons:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn from_dpad_uses_only_the_signs() {
+        assert_eq!(Direction::from_dpad(5, -3), Direction::UpRight);
+        assert_eq!(Direction::from_dpad(-128, 0), Direction::Left);
+        assert_eq!(Direction::from_dpad(0, 0), Direction::Neutral);
+    }
+
+    #[test]
+    fn mirroring_swaps_left_and_right() {
+        let cases = [
+            (Direction::Neutral, Direction::Neutral),
+            (Direction::Up, Direction::Up),
+            (Direction::UpRight, Direction::UpLeft),
+            (Direction::Right, Direction::Left),
+            (Direction::DownRight, Direction::DownLeft),
+            (Direction::Down, Direction::Down),
+            (Direction::DownLeft, Direction::DownRight),
+            (Direction::Left, Direction::Right),
+            (Direction::UpLeft, Direction::UpRight),
+        ];
+
+        for (direction, mirrored) in cases {
+            assert_eq!(direction.mirrored(), mirrored, "{direction:?}");
+            assert_eq!(mirrored.mirrored(), direction, "{direction:?}");
+        }
+    }
+
+    #[test]
+    fn diff_lists_presses_and_releases_system_buttons_first() {
+        let p1_up = Input::Button(Player::One, Button::Up);
+        let p2_b = Input::Button(Player::Two, Button::B);
+        let two_player = Input::System(SystemButton::TwoPlayer);
+
+        let mut previous = test_states::holding_inputs(&[p1_up, two_player]);
+        previous.set_extra_pressed(0, true);
+        let mut state = test_states::holding_inputs(&[p1_up, p2_b]);
+        state.set_extra_pressed(1, true);
+
+        let event = |input, pressed| InputEvent { input, pressed };
+        assert_eq!(
+            state.diff(&previous).collect::<Vec<_>>(),
+            [event(two_player, false), event(p2_b, true)]
+        );
+        assert_eq!(
+            previous.diff(&state).collect::<Vec<_>>(),
+            [event(two_player, true), event(p2_b, false)]
+        );
+        // Extras are left to the bitmask.
+        assert_eq!(state.extra_bits() ^ previous.extra_bits(), 0b11);
+    }
+
+    #[test]
+    fn diff_is_empty_without_changes_to_standard_inputs() {
+        let mut state = test_states::holding(&[Button::A]);
+        assert_eq!(state.diff(&state).count(), 0);
+
+        let previous = state;
+        state.set_extra_pressed(4, true);
+        state.connected = true;
+        assert_eq!(state.diff(&previous).count(), 0);
+    }
+
+    #[test]
+    fn display_summarizes_every_part_of_the_state() {
+        assert_eq!(
+            ControllerState::default().to_string(),
+            "disconnected | P1: - | P2: - | system: -"
+        );
+
+        let mut state = test_states::holding_inputs(&[
+            Input::Button(Player::One, Button::Up),
+            Input::Button(Player::One, Button::A),
+            Input::Button(Player::Two, Button::Left),
+            Input::System(SystemButton::OnePlayer),
+            Input::System(SystemButton::TwoPlayer),
+        ]);
+        state.connected = true;
+        assert_eq!(
+            state.to_string(),
+            "connected | P1: up a | P2: left | system: 1P 2P"
+        );
+
+        state.set_extra_pressed(0, true);
+        state.set_extra_pressed(2, true);
+        assert_eq!(
+            state.to_string(),
+            "connected | P1: up a | P2: left | system: 1P 2P | extra: 0x5"
+        );
+    }
 }
@@ -0,0 +1,90 @@
+//! Frame- or time-based durations shared by the per-frame input helpers.
+//!
+//! Helpers that care about timing take an `update(..., now_ms)` call once per frame: they count
+//! frames themselves and use `now_ms` (for example from [`rcade_sdk::clock::now_ms`]) for
+//! [`Span::Millis`]. Code that only uses [`Span::Frames`] can pass any value for `now_ms`.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A length of time, counted in frames or in milliseconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Span {
+    Frames(u32),
+    Millis(f64),
+}
+
+impl Span {
+    /// Whether at least this span has passed between `since` and `now`.
+    pub(crate) fn elapsed(self, since: Tick, now: Tick) -> bool {
+        match self {
+            Span::Frames(frames) => now.frame - since.frame >= frames as u64,
+            Span::Millis(millis) => now.millis - since.millis >= millis,
+        }
+    }
+
+    /// Whether `since` is no further back from `now` than this span.
+    pub(crate) fn contains(self, since: Tick, now: Tick) -> bool {
+        match self {
+            Span::Frames(frames) => now.frame - since.frame <= frames as u64,
+            Span::Millis(millis) => now.millis - since.millis <= millis,
+        }
+    }
+
+    /// How many whole spans fit between `since` and `now`. Zero for an empty span.
+    pub(crate) fn periods(self, since: Tick, now: Tick) -> u64 {
+        match self {
+            Span::Frames(0) => 0,
+            Span::Frames(frames) => (now.frame - since.frame) / frames as u64,
+            Span::Millis(millis) if millis <= 0.0 => 0,
+            Span::Millis(millis) => ((now.millis - since.millis) / millis).max(0.0) as u64,
+        }
+    }
+
+    /// The moment this span after `since`, for a span that has elapsed by `now`. Only the unit
+    /// the span is counted in moves on from `since`; the other one is taken from `now`.
+    pub(crate) fn advance(self, since: Tick, now: Tick) -> Tick {
+        match self {
+            Span::Frames(frames) => Tick {
+                frame: since.frame + frames as u64,
+                millis: now.millis,
+            },
+            Span::Millis(millis) => Tick {
+                frame: now.frame,
+                millis: since.millis + millis,
+            },
+        }
+    }
+
+    /// This span multiplied by `factor`, never shorter than one frame.
+    pub(crate) fn scaled(self, factor: f64) -> Span {
+        match self {
+            Span::Frames(frames) => Span::Frames(((frames as f64 * factor).round() as u32).max(1)),
+            Span::Millis(millis) => Span::Millis(millis * factor),
+        }
+    }
+}
+
+/// The moment of one update.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Tick {
+    pub(crate) frame: u64,
+    pub(crate) millis: f64,
+}
+
+/// Counts updates so helpers only need to be given the time.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Clock {
+    frame: u64,
+}
+
+impl Clock {
+    pub(crate) fn tick(&mut self, now_ms: f64) -> Tick {
+        self.frame += 1;
+        Tick {
+            frame: self.frame,
+            millis: now_ms,
+        }
+    }
+}
@@ -1,5 +1,5 @@
 /**
- * layout (each item one byte)
+ * layout: one 32-bit word, each item one bit
  * - 00 | Connected
  * - 01 | sys: 1p
  * - 02 | sys: 2p
@@ -15,74 +15,107 @@
  * - 12 | p2: right
  * - 13 | p2: a
  * - 14 | p2: b
+ * - 15..31 | extra buttons advertised by the host, in the order it lists them
+ *
+ * The word is only ever updated with Atomics.or / Atomics.and, so readers can
+ * take a consistent snapshot with a single Atomics.load and no lock.
  */
 
 const CONNECTED = 0;
-const SYSTEM_ONE_PLAYER = 1;
-const SYSTEM_TWO_PLAYER = 2;
-const PLAYER1_UP = 3;
-const PLAYER1_DOWN = 4;
-const PLAYER1_LEFT = 5;
-const PLAYER1_RIGHT = 6;
-const PLAYER1_A = 7;
-const PLAYER1_B = 8;
-const PLAYER2_UP = 9;
-const PLAYER2_DOWN = 10;
-const PLAYER2_LEFT = 11;
-const PLAYER2_RIGHT = 12;
-const PLAYER2_A = 13;
-const PLAYER2_B = 14;
+const FIRST_EXTRA = 15;
+const MAX_EXTRA = 17;
+
+const STANDARD = new Map([
+    ["system:ONE_PLAYER", 1],
+    ["system:TWO_PLAYER", 2],
+    ["button:1:UP", 3],
+    ["button:1:DOWN", 4],
+    ["button:1:LEFT", 5],
+    ["button:1:RIGHT", 6],
+    ["button:1:A", 7],
+    ["button:1:B", 8],
+    ["button:2:UP", 9],
+    ["button:2:DOWN", 10],
+    ["button:2:LEFT", 11],
+    ["button:2:RIGHT", 12],
+    ["button:2:A", 13],
+    ["button:2:B", 14],
+]);
+
+// bit of each extra button, filled in once the host advertises its buttons
+const extras = new Map();
+
+const STATE = 0;
+
+function key(type, player, button) {
+    return type === "system" ? `system:${button}` : `${type}:${player}:${button}`;
+}
+
+// buttons the main thread can name: a player button of player 1 or 2, or a
+// system button (must match `ExtraButton::from_host` in layout.rs)
+function isNamed({ type, player, button }) {
+    return typeof button === "string"
+        && ((type === "button" && (player === 1 || player === 2)) || type === "system");
+}
 
 function write(action, state) {
-    const cur_lock = lock();
+    const view = getAtomicView();
 
-    cur_lock.getDataView()[action] = state ? 1 : 0;
-    cur_lock.release();
+    if (state) {
+        Atomics.or(view, STATE, 1 << action);
+    } else {
+        Atomics.and(view, STATE, ~(1 << action));
+    }
 }
 
 function handleMessage(data) {
     const { type, player, button, pressed } = data;
 
-    if (type === "button") {
-        if (player === 1) {
-            if (button === "A") {
-                write(PLAYER1_A, pressed);
-            } else if (button === "B") {
-                write(PLAYER1_B, pressed);
-            } else if (button === "UP") {
-                write(PLAYER1_UP, pressed);
-            } else if (button === "DOWN") {
-                write(PLAYER1_DOWN, pressed);
-            } else if (button === "LEFT") {
-                write(PLAYER1_LEFT, pressed);
-            } else if (button === "RIGHT") {
-                write(PLAYER1_RIGHT, pressed);
-            }
-        } else if (player === 2) {
-            if (button === "A") {
-                write(PLAYER2_A, pressed);
-            } else if (button === "B") {
-                write(PLAYER2_B, pressed);
-            } else if (button === "UP") {
-                write(PLAYER2_UP, pressed);
-            } else if (button === "DOWN") {
-                write(PLAYER2_DOWN, pressed);
-            } else if (button === "LEFT") {
-                write(PLAYER2_LEFT, pressed);
-            } else if (button === "RIGHT") {
-                write(PLAYER2_RIGHT, pressed);
-            }
-        }
-    } else if (type === "system") {
-        if (button === "ONE_PLAYER") {
-            write(SYSTEM_ONE_PLAYER, pressed);
-        } else if (button === "TWO_PLAYER") {
-            write(SYSTEM_TWO_PLAYER, pressed);
-        }
+    if (type !== "button" && type !== "system") {
+        return;
     }
+
+    const bit = STANDARD.get(key(type, player, button)) ?? extras.get(key(type, player, button));
+    if (bit !== undefined) {
+        write(bit, pressed);
+    }
+
+    // forward to the main thread for event listeners
+    post({ type, player, button, pressed });
 }
 
-// announce connected
-function init() {
+// announce connected, then learn about any buttons beyond the standard layout
+async function init() {
     write(CONNECTED, true);
+
+    let config;
+    try {
+        config = await request({ type: "get_config" });
+    } catch {
+        // hosts that predate button advertising only have the standard layout
+        return;
+    }
+
+    // drop everything the main thread would skip before numbering, so bit
+    // FIRST_EXTRA + i is always the i-th entry of the layout it receives
+    const seen = new Set();
+    const advertised = (config.buttons ?? [])
+        .filter((entry) => {
+            const id = key(entry.type, entry.player, entry.button);
+            if (!isNamed(entry) || STANDARD.has(id) || seen.has(id)) {
+                return false;
+            }
+            seen.add(id);
+            return true;
+        })
+        .slice(0, MAX_EXTRA);
+
+    advertised.forEach(({ type, player, button }, index) => {
+        extras.set(key(type, player, button), FIRST_EXTRA + index);
+    });
+
+    post({
+        type: "layout",
+        extras: advertised.map(({ type, player, button }) => ({ type, player, button })),
+    });
 }
@@ -20,6 +20,17 @@ const MAP = {
     "Digit2": { type: "system", player: 0, button: "TWO_PLAYER" },
 } as const;
 
+// Every button the cabinet has, advertised to clients so they can support buttons beyond the
+// standard layout without an update.
+const BUTTONS = [
+    ...new Map(
+        Object.values(MAP).map(({ type, player, button }) => [
+            `${type}:${player}:${button}`,
+            { type, player, button },
+        ]),
+    ).values(),
+];
+
 export default class InputClassicPlugin implements Plugin {
     private handleInput(port: MessagePortMain, _: Electron.Event, input: Electron.Input) {
         const mapping = MAP[input.code as keyof typeof MAP];
@@ -39,8 +50,22 @@ export default class InputClassicPlugin implements Plugin {
 
     start(environment: PluginEnvironment): void {
         this.environment = environment;
+        const port = environment.getPort();
+
+        // Handle requests from client
+        port.on("message", (event) => {
+            const { type, _nonce } = event.data ?? {};
+            if (type === "get_config" && _nonce) {
+                port.postMessage({
+                    _nonce,
+                    buttons: BUTTONS,
+                });
+            }
+        });
+        port.start();
+
         this.handler = (event: Electron.Event, input: Electron.Input) => {
-            this.handleInput(environment.getPort(), event, input);
+            this.handleInput(port, event, input);
         }
 
         environment.getWebContents().on("before-input-event", this.handler);
@@ -0,0 +1,364 @@
+//! Mouse and wheel input for when no RCade host is present, so spinner games can be tested
+//! without the spinner controller.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use web_sys::{AddEventListenerOptions, Document, MouseEvent, WheelEvent};
+
+use rcade_sdk::clock;
+
+use crate::motion::Samples;
+
+/// Pixel size of a line when the browser reports wheel movement in lines.
+const WHEEL_LINE_PIXELS: f64 = 40.0;
+
+/// A pointer input that drives one player's spinner. `pixels_per_step` must be a positive
+/// number.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PointerSource {
+    /// Horizontal mouse movement, in pixels per step. Clicking the page locks the pointer so
+    /// movement is not limited by the screen edges.
+    MouseX { pixels_per_step: f64 },
+    /// Vertical mouse movement, in pixels per step (down is positive).
+    MouseY { pixels_per_step: f64 },
+    /// The scroll wheel, in pixels of scroll per step (down is positive).
+    Wheel { pixels_per_step: f64 },
+}
+
+/// Which pointer inputs stand in for each player's spinner.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointerConfig {
+    pub player1: Option<PointerSource>,
+    pub player2: Option<PointerSource>,
+    /// Steps per full rotation reported by `step_resolution`. Must not be 0.
+    pub step_resolution: u16,
+}
+
+/// The mouse drives player 1 and the wheel drives player 2, at the host's default resolution.
+impl Default for PointerConfig {
+    fn default() -> Self {
+        Self {
+            player1: Some(PointerSource::MouseX {
+                pixels_per_step: 4.0,
+            }),
+            player2: Some(PointerSource::Wheel {
+                pixels_per_step: 25.0,
+            }),
+            step_resolution: 64,
+        }
+    }
+}
+
+impl PointerSource {
+    fn pixels_per_step(self) -> f64 {
+        match self {
+            PointerSource::MouseX { pixels_per_step }
+            | PointerSource::MouseY { pixels_per_step }
+            | PointerSource::Wheel { pixels_per_step } => pixels_per_step,
+        }
+    }
+}
+
+impl PointerConfig {
+    fn validate(&self) -> Result<(), String> {
+        for source in [self.player1, self.player2].into_iter().flatten() {
+            let pixels_per_step = source.pixels_per_step();
+            // Also rejects NaN
+            if !(pixels_per_step > 0.0 && pixels_per_step.is_finite()) {
+                return Err(format!(
+                    "pixels_per_step must be a positive number, got {pixels_per_step}"
+                ));
+            }
+        }
+        if self.step_resolution == 0 {
+            return Err("step_resolution must not be 0".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Spinner {
+    /// Total steps since the fallback started.
+    position: Cell<i64>,
+    /// Movement not yet large enough for a whole step.
+    remainder: Cell<f64>,
+    samples: RefCell<Samples>,
+}
+
+struct Shared {
+    config: PointerConfig,
+    spinners: [Spinner; 2],
+}
+
+impl Shared {
+    fn feed(&self, index: usize, amount: f64, per_step: f64, now_ms: f64) {
+        let spinner = &self.spinners[index];
+        let total = spinner.remainder.get() + amount / per_step;
+        let steps = total.trunc();
+        spinner.remainder.set(total - steps);
+
+        let steps = steps as i64;
+        if steps == 0 {
+            return;
+        }
+
+        let position = spinner.position.get().saturating_add(steps);
+        spinner.position.set(position);
+        spinner.samples.borrow_mut().record(now_ms, position);
+    }
+
+    fn sources(&self) -> impl Iterator<Item = (usize, PointerSource)> {
+        [self.config.player1, self.config.player2]
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, source)| Some((index, source?)))
+    }
+
+    fn uses_mouse(&self) -> bool {
+        self.sources().any(|(_, source)| {
+            matches!(
+                source,
+                PointerSource::MouseX { .. } | PointerSource::MouseY { .. }
+            )
+        })
+    }
+
+    fn uses_wheel(&self) -> bool {
+        self.sources()
+            .any(|(_, source)| matches!(source, PointerSource::Wheel { .. }))
+    }
+}
+
+pub(crate) struct PointerInput {
+    shared: Rc<Shared>,
+    document: Document,
+    on_mouse_move: Closure<dyn FnMut(MouseEvent)>,
+    on_wheel: Closure<dyn FnMut(WheelEvent)>,
+    on_click: Closure<dyn FnMut(MouseEvent)>,
+}
+
+impl PointerInput {
+    pub(crate) fn new(config: PointerConfig) -> Result<Self, JsValue> {
+        config.validate().map_err(|err| JsValue::from_str(&err))?;
+
+        let document = web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or_else(|| JsValue::from_str("pointer input needs a document"))?;
+
+        let shared = Rc::new(Shared {
+            config,
+            spinners: Default::default(),
+        });
+
+        let move_shared = shared.clone();
+        let on_mouse_move = Closure::wrap(Box::new(move |event: MouseEvent| {
+            for (index, source) in move_shared.sources() {
+                match source {
+                    PointerSource::MouseX { pixels_per_step } => move_shared.feed(
+                        index,
+                        event.movement_x() as f64,
+                        pixels_per_step,
+                        clock::now_ms(),
+                    ),
+                    PointerSource::MouseY { pixels_per_step } => move_shared.feed(
+                        index,
+                        event.movement_y() as f64,
+                        pixels_per_step,
+                        clock::now_ms(),
+                    ),
+                    PointerSource::Wheel { .. } => {}
+                }
+            }
+        }) as Box<dyn FnMut(MouseEvent)>);
+
+        let wheel_shared = shared.clone();
+        let on_wheel = Closure::wrap(Box::new(move |event: WheelEvent| {
+            let scale = match event.delta_mode() {
+                WheelEvent::DOM_DELTA_LINE => WHEEL_LINE_PIXELS,
+                _ => 1.0,
+            };
+            for (index, source) in wheel_shared.sources() {
+                if let PointerSource::Wheel { pixels_per_step } = source {
+                    // Keep the page from scrolling while the wheel turns a spinner
+                    event.prevent_default();
+                    wheel_shared.feed(
+                        index,
+                        event.delta_y() * scale,
+                        pixels_per_step,
+                        clock::now_ms(),
+                    );
+                }
+            }
+        }) as Box<dyn FnMut(WheelEvent)>);
+
+        let lock_document = document.clone();
+        let on_click = Closure::wrap(Box::new(move |_: MouseEvent| {
+            if lock_document.pointer_lock_element().is_none()
+                && let Some(body) = lock_document.body()
+            {
+                body.request_pointer_lock();
+            }
+        }) as Box<dyn FnMut(MouseEvent)>);
+
+        // Only listen for what the config uses, so the page keeps its own scrolling and clicks
+        // when no spinner is bound to them
+        if shared.uses_mouse() {
+            document.add_event_listener_with_callback(
+                "mousemove",
+                on_mouse_move.as_ref().unchecked_ref(),
+            )?;
+            document
+                .add_event_listener_with_callback("click", on_click.as_ref().unchecked_ref())?;
+        }
+        if shared.uses_wheel() {
+            // Wheel listeners on the document are passive by default, which ignores
+            // `prevent_default`
+            let wheel_options = AddEventListenerOptions::new();
+            wheel_options.set_passive(false);
+            document.add_event_listener_with_callback_and_add_event_listener_options(
+                "wheel",
+                on_wheel.as_ref().unchecked_ref(),
+                &wheel_options,
+            )?;
+        }
+
+        Ok(Self {
+            shared,
+            document,
+            on_mouse_move,
+            on_wheel,
+            on_click,
+        })
+    }
+
+    pub(crate) fn position(&self, index: usize) -> i64 {
+        self.shared.spinners[index].position.get()
+    }
+
+    pub(crate) fn samples(&self, index: usize) -> Samples {
+        self.shared.spinners[index].samples.borrow().clone()
+    }
+
+    pub(crate) fn step_resolution(&self) -> u16 {
+        self.shared.config.step_resolution
+    }
+}
+
+impl Drop for PointerInput {
+    fn drop(&mut self) {
+        let _ = self.document.remove_event_listener_with_callback(
+            "mousemove",
+            self.on_mouse_move.as_ref().unchecked_ref(),
+        );
+        let _ = self
+            .document
+            .remove_event_listener_with_callback("wheel", self.on_wheel.as_ref().unchecked_ref());
+        let _ = self
+            .document
+            .remove_event_listener_with_callback("click", self.on_click.as_ref().unchecked_ref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shared(config: PointerConfig) -> Shared {
+        Shared {
+            config,
+            spinners: Default::default(),
+        }
+    }
+
+    #[test]
+    fn the_default_config_is_valid() {
+        assert_eq!(PointerConfig::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn rejects_scales_that_are_not_positive_numbers() {
+        for pixels_per_step in [0.0, -4.0, f64::NAN, f64::INFINITY] {
+            let config = PointerConfig {
+                player2: Some(PointerSource::MouseY { pixels_per_step }),
+                ..Default::default()
+            };
+            assert!(config.validate().is_err(), "{pixels_per_step}");
+        }
+    }
+
+    #[test]
+    fn rejects_a_zero_step_resolution() {
+        let config = PointerConfig {
+            step_resolution: 0,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn unbound_players_are_not_validated_or_listened_to() {
+        let config = PointerConfig {
+            player1: None,
+            player2: Some(PointerSource::MouseX {
+                pixels_per_step: 2.0,
+            }),
+            ..Default::default()
+        };
+        assert_eq!(config.validate(), Ok(()));
+
+        let shared = shared(config);
+        assert_eq!(
+            shared.sources().collect::<Vec<_>>(),
+            [(1, config.player2.unwrap())]
+        );
+        assert!(shared.uses_mouse());
+        assert!(!shared.uses_wheel());
+    }
+
+    #[test]
+    fn listens_to_the_wheel_only_when_a_player_uses_it() {
+        assert!(shared(PointerConfig::default()).uses_wheel());
+        assert!(
+            !shared(PointerConfig {
+                player2: None,
+                ..Default::default()
+            })
+            .uses_wheel()
+        );
+    }
+
+    #[test]
+    fn partial_steps_carry_over() {
+        let shared = shared(PointerConfig::default());
+        for (now, pixels) in [(0.0, 3.0), (16.0, 3.0), (32.0, 3.0), (48.0, -1.0)] {
+            shared.feed(0, pixels, 4.0, now);
+        }
+
+        let spinner = &shared.spinners[0];
+        assert_eq!(spinner.position.get(), 2);
+        assert_eq!(spinner.remainder.get(), 0.0);
+        assert_eq!(shared.spinners[1].position.get(), 0);
+    }
+
+    #[test]
+    fn moving_back_steps_backwards() {
+        let shared = shared(PointerConfig::default());
+        shared.feed(1, -10.0, 4.0, 0.0);
+
+        assert_eq!(shared.spinners[1].position.get(), -2);
+        assert_eq!(shared.spinners[1].remainder.get(), -0.5);
+    }
+
+    #[test]
+    fn the_position_saturates_instead_of_overflowing() {
+        let shared = shared(PointerConfig::default());
+        shared.spinners[0].position.set(i64::MAX - 1);
+        shared.feed(0, 40.0, 4.0, 0.0);
+
+        assert_eq!(shared.spinners[0].position.get(), i64::MAX);
+    }
+}
@@ -1,82 +1,477 @@
+mod fallback;
+mod motion;
+
+use std::cell::Cell;
+use std::fmt;
+
+use rcade_sdk::clock;
+use rcade_sdk::device::{Capabilities, InputDevice};
+pub use rcade_sdk::player::Player;
+use rcade_sdk::replay::Frame;
 use rcade_sdk::{channel::PluginChannel, shmem_runner::PluginSharedMemoryRunner};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::JsValue;
 
+use crate::fallback::PointerInput;
+pub use crate::fallback::{PointerConfig, PointerSource};
+pub use crate::motion::SpinnerMotion;
+use crate::motion::{
+    DEFAULT_FILTER_WINDOW_MS, MAX_FILTER_WINDOW_MS, SAMPLE_COUNT, SAMPLE_SIZE, Samples,
+};
+
 // Shared memory layout (must match worker.js)
 const CONNECTED: usize = 0;
-const SPINNER1_DELTA: usize = 2;
-const SPINNER2_DELTA: usize = 4;
-const STEP_RES: usize = 6;
-const SPINNER1_ANGLE: usize = 8;
-const SPINNER2_ANGLE: usize = 12;
+const SPINNER_COUNT: usize = 1;
+const STEP_RES: usize = 2;
+const SPINNER1_POSITION: usize = 8;
+const SPINNER2_POSITION: usize = 16;
+const SPINNER1_SAMPLES_WRITTEN: usize = 32;
+const SPINNER2_SAMPLES_WRITTEN: usize = 36;
+const SPINNER1_SAMPLES: usize = 40;
+const SPINNER2_SAMPLES: usize = SPINNER1_SAMPLES + SAMPLE_COUNT * SAMPLE_SIZE;
+const MEMORY_SIZE: usize = SPINNER2_SAMPLES + SAMPLE_COUNT * SAMPLE_SIZE;
+
+/// Spinners the shared memory has room for, one per player.
+const MAX_SPINNERS: u8 = 2;
+
+/// Largest step delta `step_delta` reports in either direction.
+const MAX_DELTA: i64 = 1000;
+
+enum Backend {
+    Plugin(PluginSharedMemoryRunner),
+    Pointer(PointerInput),
+}
 
 /// Controller for spinner input devices.
 ///
 /// Poll `step_delta(player)` each frame to get accumulated movement (resets after read).
-/// Use `step_resolution()` to convert steps to rotations.
+/// Use `step_resolution()` to convert steps to rotations, or `snapshot()` to read everything at
+/// once. Parts of a game that each need their own movement can read it through separate
+/// [`SpinnerCursor`]s instead.
 pub struct SpinnerController {
-    runner: PluginSharedMemoryRunner,
+    backend: Backend,
+    /// Read by `step_delta` and `snapshot`.
+    cursor: Cell<SpinnerCursor>,
+    /// Positions at the last `reset`, which `position` and `angle` count from.
+    origins: Cell<[i64; 2]>,
+    filter_window_ms: Cell<f64>,
 }
 
 impl SpinnerController {
+    /// Longest window [`SpinnerController::set_filter_window_ms`] accepts.
+    pub const MAX_FILTER_WINDOW_MS: f64 = MAX_FILTER_WINDOW_MS;
+
+    /// Connects to the `@rcade/input-spinners` host plugin, falling back to the mouse and wheel
+    /// with the default [`PointerConfig`] when there is no host. See
+    /// [`PluginChannel::acquire_if_hosted`] for when that happens.
     pub async fn acquire() -> Result<Self, JsValue> {
-        let channel = PluginChannel::acquire("@rcade/input-spinners", "1.0.0").await?;
-        let runner = PluginSharedMemoryRunner::spawn(include_str!("./worker.js"), channel, 16)?;
-        Ok(Self { runner })
+        Self::acquire_with_fallback(PointerConfig::default()).await
+    }
+
+    /// Like [`SpinnerController::acquire`], but with the given pointer mapping and scaling for
+    /// the fallback.
+    ///
+    /// Fails without a host if `fallback` has a `pixels_per_step` that isn't a positive number,
+    /// or a `step_resolution` of 0.
+    pub async fn acquire_with_fallback(fallback: PointerConfig) -> Result<Self, JsValue> {
+        let channel = PluginChannel::acquire_if_hosted("@rcade/input-spinners", "1.0.0").await?;
+
+        let backend = match channel {
+            Some(channel) => Backend::Plugin(PluginSharedMemoryRunner::spawn(
+                include_str!("./worker.js"),
+                channel,
+                MEMORY_SIZE as u32,
+            )?),
+            None => Backend::Pointer(PointerInput::new(fallback)?),
+        };
+
+        Ok(Self {
+            backend,
+            cursor: Cell::default(),
+            origins: Cell::default(),
+            filter_window_ms: Cell::new(DEFAULT_FILTER_WINDOW_MS),
+        })
+    }
+
+    /// Whether spinner movement comes from the mouse and wheel instead of the RCade host.
+    pub fn is_pointer_fallback(&self) -> bool {
+        matches!(self.backend, Backend::Pointer(_))
     }
 
     pub fn connected(&self) -> bool {
-        let lock = self.runner.lock_blocking();
-        lock.data_view().at(CONNECTED as i32).unwrap_or(0) != 0
+        self.read().connected
+    }
+
+    /// How many spinners the host has, numbered from player 1. Until the host has answered
+    /// (and with the pointer fallback) this is 2, one for each player.
+    pub fn spinner_count(&self) -> u8 {
+        self.read().spinner_count
+    }
+
+    /// Reads both spinners under a single lock, so deltas, positions and angles come from the
+    /// same host update. Both step deltas reset to 0, as with `step_delta`. Players without a
+    /// spinner read as not moving.
+    pub fn snapshot(&self) -> SpinnerState {
+        let Reading {
+            connected,
+            spinner_count,
+            step_resolution,
+            positions,
+        } = self.read();
+
+        let mut cursor = self.cursor.get();
+        let [player1_delta, player2_delta] =
+            [0, 1].map(|index| clamp_delta(cursor.advance(index, positions[index])));
+        self.cursor.set(cursor);
+
+        let origins = self.origins.get();
+        let [player1_position, player2_position] =
+            [0, 1].map(|index| positions[index] - origins[index]);
+
+        SpinnerState {
+            connected,
+            spinner_count,
+            step_resolution,
+            player1_delta,
+            player2_delta,
+            player1_position,
+            player2_position,
+            player1_angle: angle_at(player1_position, step_resolution),
+            player2_angle: angle_at(player2_position, step_resolution),
+        }
     }
 
     /// Returns accumulated step delta since last call, then resets to 0.
-    pub fn step_delta(&self, player: u8) -> i16 {
-        let offset = match player {
-            1 => SPINNER1_DELTA,
-            2 => SPINNER2_DELTA,
-            _ => return 0,
-        };
-        let lock = self.runner.lock_blocking();
-        let view = lock.data_view();
-        let val = read_i16(&view, offset);
-        write_i16(&view, offset, 0);
-        val
+    pub fn step_delta(&self, player: Player) -> Result<i16, SpinnerError> {
+        let mut cursor = self.cursor.get();
+        let delta = cursor.step_delta(self, player)?;
+        self.cursor.set(cursor);
+        Ok(clamp_delta(delta))
+    }
+
+    /// A new [`SpinnerCursor`] that counts movement from now on.
+    pub fn cursor(&self) -> SpinnerCursor {
+        SpinnerCursor {
+            positions: self.read().positions,
+        }
+    }
+
+    /// Everything but the samples, under a single lock.
+    fn read(&self) -> Reading {
+        match &self.backend {
+            Backend::Plugin(runner) => {
+                let lock = runner.lock_blocking();
+                let view = lock.data_view();
+                Reading {
+                    connected: view.at(CONNECTED as i32).unwrap_or(0) != 0,
+                    spinner_count: spinner_count(&view),
+                    step_resolution: read_u16(&view, STEP_RES),
+                    positions: [
+                        read_i64(&view, SPINNER1_POSITION),
+                        read_i64(&view, SPINNER2_POSITION),
+                    ],
+                }
+            }
+            Backend::Pointer(pointer) => Reading {
+                connected: true,
+                spinner_count: MAX_SPINNERS,
+                step_resolution: pointer.step_resolution(),
+                positions: [pointer.position(0), pointer.position(1)],
+            },
+        }
+    }
+
+    /// The player's recent samples and the step resolution, under a single lock.
+    fn samples(&self, player: Player) -> Result<(Samples, u16), SpinnerError> {
+        match &self.backend {
+            Backend::Plugin(runner) => {
+                let lock = runner.lock_blocking();
+                let view = lock.data_view();
+                let index = spinner_index(player, spinner_count(&view))?;
+                let (written, start) = [
+                    (SPINNER1_SAMPLES_WRITTEN, SPINNER1_SAMPLES),
+                    (SPINNER2_SAMPLES_WRITTEN, SPINNER2_SAMPLES),
+                ][index];
+                let mut bytes = vec![0; SAMPLE_COUNT * SAMPLE_SIZE];
+                view.subarray(start as u32, (start + bytes.len()) as u32)
+                    .copy_to(&mut bytes);
+                Ok((
+                    Samples::from_bytes(&bytes, read_u32(&view, written)),
+                    read_u16(&view, STEP_RES),
+                ))
+            }
+            Backend::Pointer(pointer) => {
+                let index = spinner_index(player, MAX_SPINNERS)?;
+                Ok((pointer.samples(index), pointer.step_resolution()))
+            }
+        }
     }
 
     /// Steps per full rotation.
     pub fn step_resolution(&self) -> u16 {
-        let lock = self.runner.lock_blocking();
-        read_u16(&lock.data_view(), STEP_RES)
+        self.read().step_resolution
     }
 
-    /// Current angle in radians, normalized to [-π, π].
-    pub fn angle(&self, player: u8) -> f32 {
-        let offset = match player {
-            1 => SPINNER1_ANGLE,
-            2 => SPINNER2_ANGLE,
-            _ => return 0.0,
-        };
-        let lock = self.runner.lock_blocking();
-        read_f32(&lock.data_view(), offset)
+    /// Steps the player's spinner has moved since connecting or the last `reset`, counting
+    /// every full rotation.
+    pub fn position(&self, player: Player) -> Result<i64, SpinnerError> {
+        Ok(self.position_and_resolution(player)?.0)
     }
 
-    /// Reset angle to 0.
-    pub fn reset(&self, player: u8) {
-        let offset = match player {
-            1 => SPINNER1_ANGLE,
-            2 => SPINNER2_ANGLE,
-            _ => return,
-        };
-        let lock = self.runner.lock_blocking();
-        write_f32(&lock.data_view(), offset, 0.0);
+    /// Full rotations since connecting or the last `reset`, rounded down (so a quarter turn
+    /// backwards is -1).
+    pub fn revolutions(&self, player: Player) -> Result<i64, SpinnerError> {
+        let (position, resolution) = self.position_and_resolution(player)?;
+        if resolution == 0 {
+            return Ok(0);
+        }
+        Ok(position.div_euclid(resolution as i64))
+    }
+
+    /// Current angle in radians, normalized to [-π, π]. Worked out from the exact position, so
+    /// it doesn't drift however long the spinner turns.
+    pub fn angle(&self, player: Player) -> Result<f32, SpinnerError> {
+        let (position, resolution) = self.position_and_resolution(player)?;
+        Ok(angle_at(position, resolution))
+    }
+
+    /// The player's position (as with `position`) and the step resolution, read together.
+    fn position_and_resolution(&self, player: Player) -> Result<(i64, u16), SpinnerError> {
+        let reading = self.read();
+        let index = reading.index(player)?;
+        Ok((
+            reading.positions[index] - self.origins.get()[index],
+            reading.step_resolution,
+        ))
+    }
+
+    /// How fast the player's spinner is turning, averaged over the filter window.
+    pub fn motion(&self, player: Player) -> Result<SpinnerMotion, SpinnerError> {
+        let (samples, step_resolution) = self.samples(player)?;
+        Ok(SpinnerMotion::estimate(
+            &samples,
+            clock::now_ms(),
+            self.filter_window_ms.get(),
+            step_resolution,
+        ))
+    }
+
+    /// Milliseconds of movement that `motion` averages over.
+    pub fn filter_window_ms(&self) -> f64 {
+        self.filter_window_ms.get()
+    }
+
+    /// Sets how many milliseconds of movement `motion` averages over (100 by default). Longer
+    /// windows are smoother but slower to follow changes. The window must be positive and at
+    /// most [`SpinnerController::MAX_FILTER_WINDOW_MS`], the history the samples are sure to
+    /// cover.
+    pub fn set_filter_window_ms(&self, window_ms: f64) -> Result<(), SpinnerError> {
+        if !motion::is_valid_filter_window(window_ms) {
+            return Err(SpinnerError::InvalidFilterWindow);
+        }
+
+        self.filter_window_ms.set(window_ms);
+        Ok(())
+    }
+
+    /// Reset position, revolutions and angle to 0. Step deltas and cursors are unaffected.
+    pub fn reset(&self, player: Player) -> Result<(), SpinnerError> {
+        let reading = self.read();
+        let index = reading.index(player)?;
+        let mut origins = self.origins.get();
+        origins[index] = reading.positions[index];
+        self.origins.set(origins);
+        Ok(())
     }
 }
 
-fn read_i16(view: &js_sys::Uint8Array, offset: usize) -> i16 {
-    i16::from_le_bytes([
-        view.at(offset as i32).unwrap_or(0),
-        view.at((offset + 1) as i32).unwrap_or(0),
-    ])
+/// The shared memory at one instant, see [`SpinnerController::read`].
+struct Reading {
+    connected: bool,
+    spinner_count: u8,
+    step_resolution: u16,
+    /// Total steps each spinner has moved since connecting.
+    positions: [i64; 2],
+}
+
+impl Reading {
+    fn index(&self, player: Player) -> Result<usize, SpinnerError> {
+        spinner_index(player, self.spinner_count)
+    }
+}
+
+/// The slot of `player`'s spinner, if the host has one.
+fn spinner_index(player: Player, spinner_count: u8) -> Result<usize, SpinnerError> {
+    if player.number() > spinner_count {
+        return Err(SpinnerError::NoSuchPlayer {
+            player,
+            spinner_count,
+        });
+    }
+    Ok(player.index())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpinnerError {
+    /// The host has fewer spinners than this player's number.
+    NoSuchPlayer { player: Player, spinner_count: u8 },
+    /// A filter window that is zero, negative, NaN or longer than
+    /// [`SpinnerController::MAX_FILTER_WINDOW_MS`].
+    InvalidFilterWindow,
+}
+
+impl fmt::Display for SpinnerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpinnerError::NoSuchPlayer {
+                player,
+                spinner_count,
+            } => write!(
+                f,
+                "no spinner for player {}, the host has {spinner_count}",
+                player.number()
+            ),
+            SpinnerError::InvalidFilterWindow => {
+                write!(
+                    f,
+                    "the filter window must be a positive number of milliseconds, at most \
+                     {MAX_FILTER_WINDOW_MS}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SpinnerError {}
+
+/// An independent reader of spinner movement. Each cursor remembers where it last read the
+/// spinners, so the UI and gameplay can both see every step without taking movement from each
+/// other.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SpinnerCursor {
+    positions: [i64; 2],
+}
+
+impl SpinnerCursor {
+    /// Steps the player's spinner has moved since this cursor last read it.
+    pub fn step_delta(
+        &mut self,
+        spinners: &SpinnerController,
+        player: Player,
+    ) -> Result<i64, SpinnerError> {
+        let reading = spinners.read();
+        let index = reading.index(player)?;
+        Ok(self.advance(index, reading.positions[index]))
+    }
+
+    fn advance(&mut self, index: usize, position: i64) -> i64 {
+        position - std::mem::replace(&mut self.positions[index], position)
+    }
+}
+
+/// Both spinners as read at one instant by [`SpinnerController::snapshot`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SpinnerState {
+    pub connected: bool,
+    /// How many players have a spinner, as with `SpinnerController::spinner_count`.
+    pub spinner_count: u8,
+    /// Steps per full rotation.
+    pub step_resolution: u16,
+    /// Accumulated step delta since the last read.
+    pub player1_delta: i16,
+    pub player2_delta: i16,
+    /// Steps since connecting or the last reset, as with `SpinnerController::position`.
+    pub player1_position: i64,
+    pub player2_position: i64,
+    /// Angle in radians, normalized to [-π, π].
+    pub player1_angle: f32,
+    pub player2_angle: f32,
+}
+
+/// Spinner movement read during one frame, as recorded by `rcade_sdk::replay`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SpinnerFrame {
+    pub player1_delta: i16,
+    pub player2_delta: i16,
+}
+
+impl Frame for SpinnerFrame {
+    const SIZE: usize = 4;
+
+    fn encode(&self, out: &mut [u8]) {
+        out[0..2].copy_from_slice(&self.player1_delta.to_le_bytes());
+        out[2..4].copy_from_slice(&self.player2_delta.to_le_bytes());
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        SpinnerFrame {
+            player1_delta: i16::from_le_bytes([bytes[0], bytes[1]]),
+            player2_delta: i16::from_le_bytes([bytes[2], bytes[3]]),
+        }
+    }
+}
+
+/// Updating reads (and resets) both players' step deltas.
+impl InputDevice for SpinnerController {
+    type Frame = SpinnerFrame;
+
+    fn connected(&self) -> bool {
+        SpinnerController::connected(self)
+    }
+
+    fn is_fallback(&self) -> bool {
+        self.is_pointer_fallback()
+    }
+
+    fn player_count(&self) -> u8 {
+        self.spinner_count()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            spinners_per_player: 1,
+            ..Capabilities::default()
+        }
+    }
+
+    fn update(&mut self) -> SpinnerFrame {
+        let state = self.snapshot();
+        SpinnerFrame {
+            player1_delta: state.player1_delta,
+            player2_delta: state.player2_delta,
+        }
+    }
+}
+
+/// The angle of `position` in radians, normalized to [-π, π].
+fn angle_at(position: i64, step_resolution: u16) -> f32 {
+    if step_resolution == 0 {
+        return 0.0;
+    }
+    let resolution = step_resolution as i64;
+    // Nearest to zero of the two ways around, so half a turn is π
+    let mut steps = position.rem_euclid(resolution);
+    if steps * 2 > resolution {
+        steps -= resolution;
+    }
+    (steps as f64 / resolution as f64 * std::f64::consts::TAU) as f32
+}
+
+fn clamp_delta(delta: i64) -> i16 {
+    delta.clamp(-MAX_DELTA, MAX_DELTA) as i16
+}
+
+/// Both spinners until the host has said otherwise.
+fn spinner_count(view: &js_sys::Uint8Array) -> u8 {
+    if view.at(CONNECTED as i32).unwrap_or(0) == 0 {
+        return MAX_SPINNERS;
+    }
+    view.at(SPINNER_COUNT as i32)
+        .unwrap_or(MAX_SPINNERS)
+        .min(MAX_SPINNERS)
 }
 
 fn read_u16(view: &js_sys::Uint8Array, offset: usize) -> u16 {
@@ -86,14 +481,8 @@ fn read_u16(view: &js_sys::Uint8Array, offset: usize) -> u16 {
     ])
 }
 
-fn write_i16(view: &js_sys::Uint8Array, offset: usize, val: i16) {
-    let b = val.to_le_bytes();
-    view.set_index(offset as u32, b[0]);
-    view.set_index((offset + 1) as u32, b[1]);
-}
-
-fn read_f32(view: &js_sys::Uint8Array, offset: usize) -> f32 {
-    f32::from_le_bytes([
+fn read_u32(view: &js_sys::Uint8Array, offset: usize) -> u32 {
+    u32::from_le_bytes([
         view.at(offset as i32).unwrap_or(0),
         view.at((offset + 1) as i32).unwrap_or(0),
         view.at((offset + 2) as i32).unwrap_or(0),
@@ -101,10 +490,91 @@ fn read_f32(view: &js_sys::Uint8Array, offset: usize) -> f32 {
     ])
 }
 
-fn write_f32(view: &js_sys::Uint8Array, offset: usize, val: f32) {
-    let b = val.to_le_bytes();
-    view.set_index(offset as u32, b[0]);
-    view.set_index((offset + 1) as u32, b[1]);
-    view.set_index((offset + 2) as u32, b[2]);
-    view.set_index((offset + 3) as u32, b[3]);
+fn read_i64(view: &js_sys::Uint8Array, offset: usize) -> i64 {
+    let mut bytes = [0; 8];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = view.at((offset + i) as i32).unwrap_or(0);
+    }
+    i64::from_le_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    use super::*;
+
+    #[test]
+    fn angles_cover_a_full_turn() {
+        assert_eq!(angle_at(0, 64), 0.0);
+        assert_eq!(angle_at(16, 64), FRAC_PI_2);
+        assert_eq!(angle_at(32, 64), PI);
+        assert_eq!(angle_at(48, 64), -FRAC_PI_2);
+        assert_eq!(angle_at(-16, 64), -FRAC_PI_2);
+    }
+
+    #[test]
+    fn angles_stay_exact_after_many_revolutions() {
+        let revolutions = 1_000_000_000_000;
+        for steps in [0, 5, 16, 32, 63] {
+            let expected = angle_at(steps, 64);
+            assert_eq!(angle_at(revolutions * 64 + steps, 64), expected);
+            assert_eq!(angle_at(-revolutions * 64 + steps, 64), expected);
+        }
+    }
+
+    #[test]
+    fn angles_stay_in_range_at_the_extremes() {
+        for position in [i64::MIN, i64::MIN + 1, i64::MAX - 1, i64::MAX] {
+            let angle = angle_at(position, 600);
+            assert!((-PI..=PI).contains(&angle), "{angle} out of range");
+        }
+        assert_eq!(angle_at(12, 0), 0.0);
+    }
+
+    #[test]
+    fn players_beyond_the_spinner_count_have_no_spinner() {
+        assert_eq!(spinner_index(Player::One, 1), Ok(0));
+        assert_eq!(spinner_index(Player::Two, 2), Ok(1));
+        assert_eq!(
+            spinner_index(Player::Two, 1),
+            Err(SpinnerError::NoSuchPlayer {
+                player: Player::Two,
+                spinner_count: 1,
+            })
+        );
+        assert!(spinner_index(Player::One, 0).is_err());
+    }
+
+    #[test]
+    fn a_cursor_reports_movement_since_its_last_read() {
+        let mut cursor = SpinnerCursor::default();
+        assert_eq!(cursor.advance(0, 10), 10);
+        assert_eq!(cursor.advance(0, 10), 0);
+        assert_eq!(cursor.advance(0, 4), -6);
+        // Each spinner is counted separately.
+        assert_eq!(cursor.advance(1, -3), -3);
+        assert_eq!(cursor.advance(0, 5), 1);
+    }
+
+    #[test]
+    fn cursors_do_not_take_movement_from_each_other() {
+        let mut ui = SpinnerCursor::default();
+        let mut gameplay = SpinnerCursor::default();
+
+        assert_eq!(ui.advance(0, 3), 3);
+        assert_eq!(ui.advance(0, 7), 4);
+        assert_eq!(gameplay.advance(0, 7), 7);
+        assert_eq!(gameplay.advance(0, 8), 1);
+    }
+
+    #[test]
+    fn step_deltas_are_clamped_but_cursors_are_not() {
+        let mut cursor = SpinnerCursor::default();
+        let delta = cursor.advance(1, 5000);
+        assert_eq!(delta, 5000);
+        assert_eq!(clamp_delta(delta), 1000);
+        assert_eq!(clamp_delta(-delta), -1000);
+        assert_eq!(clamp_delta(12), 12);
+    }
 }
@@ -0,0 +1,263 @@
+//! Spin speed estimated from timestamped position samples.
+//!
+//! The worker (or the pointer fallback) records `(time, position)` each time a spinner moves,
+//! merging samples that arrive within a few milliseconds of each other. Velocity is the average
+//! over the filter window up to now, so it falls back to zero once the spinner stops, and
+//! acceleration compares the two halves of the window.
+
+use std::collections::VecDeque;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Samples kept per spinner (must match worker.js).
+pub(crate) const SAMPLE_COUNT: usize = 128;
+/// Bytes per sample in shared memory: time (f64) and position (i64).
+pub(crate) const SAMPLE_SIZE: usize = 16;
+/// Samples closer than this to the one before the last replace the last one (must match
+/// worker.js), so every other sample is at least this far apart.
+const SAMPLE_INTERVAL_MS: f64 = 4.0;
+/// Longest filter window the samples are sure to cover. [`SAMPLE_COUNT`] samples span at least
+/// `(SAMPLE_COUNT - 2) / 2` intervals, 252 ms.
+pub(crate) const MAX_FILTER_WINDOW_MS: f64 = 250.0;
+
+/// Filter window used until `set_filter_window_ms` changes it.
+pub(crate) const DEFAULT_FILTER_WINDOW_MS: f64 = 100.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Sample {
+    /// Milliseconds since the epoch, on the clock of [`rcade_sdk::clock::now_ms`].
+    pub(crate) time_ms: f64,
+    pub(crate) position: i64,
+}
+
+/// A spinner's recent samples, oldest first.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Samples {
+    samples: VecDeque<Sample>,
+    /// Whether older samples were dropped, so the position before the oldest one is unknown.
+    wrapped: bool,
+}
+
+impl Samples {
+    /// Reads a sample ring as laid out by the worker, `written` being the number of samples
+    /// it has written so far.
+    pub(crate) fn from_bytes(bytes: &[u8], written: u32) -> Self {
+        let written = written as usize;
+        let read = |slot: usize| {
+            let sample = &bytes[slot * SAMPLE_SIZE..][..SAMPLE_SIZE];
+            Sample {
+                time_ms: f64::from_le_bytes(sample[0..8].try_into().unwrap()),
+                position: i64::from_le_bytes(sample[8..16].try_into().unwrap()),
+            }
+        };
+
+        Self {
+            samples: (written.saturating_sub(SAMPLE_COUNT)..written)
+                .map(|index| read(index % SAMPLE_COUNT))
+                .collect(),
+            wrapped: written > SAMPLE_COUNT,
+        }
+    }
+
+    /// Records that the spinner reached `position` at `time_ms`, as the worker does.
+    pub(crate) fn record(&mut self, time_ms: f64, position: i64) {
+        let sample = Sample { time_ms, position };
+        let len = self.samples.len();
+        if len >= 2 && time_ms - self.samples[len - 2].time_ms < SAMPLE_INTERVAL_MS {
+            self.samples[len - 1] = sample;
+            return;
+        }
+
+        if len == SAMPLE_COUNT {
+            self.samples.pop_front();
+            self.wrapped = true;
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// The time and position to measure from at `time_ms`: the position then or, if the
+    /// samples don't reach back that far, the oldest one known.
+    fn at(&self, time_ms: f64) -> (f64, i64) {
+        match self.samples.iter().rev().find(|s| s.time_ms <= time_ms) {
+            Some(sample) => (time_ms, sample.position),
+            None => match self.samples.front() {
+                Some(oldest) if self.wrapped => (oldest.time_ms, oldest.position),
+                // Every spinner starts at position 0
+                _ => (time_ms, 0),
+            },
+        }
+    }
+
+    /// Average steps per second between `from_ms` and `to_ms`.
+    fn velocity(&self, from_ms: f64, to_ms: f64) -> f64 {
+        let (from_ms, from) = self.at(from_ms);
+        let (to_ms, to) = self.at(to_ms);
+        if to_ms <= from_ms {
+            return 0.0;
+        }
+        (to - from) as f64 / ((to_ms - from_ms) / 1000.0)
+    }
+}
+
+/// Whether `window_ms` can be used as a filter window: a positive number of milliseconds, no
+/// longer than [`MAX_FILTER_WINDOW_MS`].
+pub(crate) fn is_valid_filter_window(window_ms: f64) -> bool {
+    // Also rejects NaN
+    window_ms > 0.0 && window_ms <= MAX_FILTER_WINDOW_MS
+}
+
+/// How fast a spinner is turning, smoothed over the controller's filter window.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SpinnerMotion {
+    /// Positive in the direction of positive step deltas.
+    pub steps_per_second: f64,
+    pub radians_per_second: f64,
+    pub steps_per_second_squared: f64,
+    pub radians_per_second_squared: f64,
+}
+
+impl SpinnerMotion {
+    pub(crate) fn estimate(
+        samples: &Samples,
+        now_ms: f64,
+        window_ms: f64,
+        step_resolution: u16,
+    ) -> Self {
+        let half = window_ms / 2.0;
+        let velocity = samples.velocity(now_ms - window_ms, now_ms);
+        let acceleration = (samples.velocity(now_ms - half, now_ms)
+            - samples.velocity(now_ms - window_ms, now_ms - half))
+            / (half / 1000.0);
+
+        let radians_per_step = if step_resolution == 0 {
+            0.0
+        } else {
+            std::f64::consts::TAU / step_resolution as f64
+        };
+
+        Self {
+            steps_per_second: velocity,
+            radians_per_second: velocity * radians_per_step,
+            steps_per_second_squared: acceleration,
+            radians_per_second_squared: acceleration * radians_per_step,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    /// Samples every 10 ms for 500 ms, at 100 steps per second up to 400 ms and 200 after.
+    fn speeding_up() -> Samples {
+        let mut samples = Samples::default();
+        for time in (10..=500).step_by(10) {
+            let position = if time <= 400 {
+                time / 10
+            } else {
+                40 + (time - 400) / 5
+            };
+            samples.record(time as f64, position);
+        }
+        samples
+    }
+
+    #[test]
+    fn steady_turning_has_no_acceleration() {
+        let motion = SpinnerMotion::estimate(&speeding_up(), 400.0, 100.0, 64);
+
+        assert_close(motion.steps_per_second, 100.0);
+        assert_close(
+            motion.radians_per_second,
+            100.0 * std::f64::consts::TAU / 64.0,
+        );
+        assert_close(motion.steps_per_second_squared, 0.0);
+    }
+
+    #[test]
+    fn speeding_up_is_measured_across_the_window() {
+        let motion = SpinnerMotion::estimate(&speeding_up(), 500.0, 200.0, 64);
+
+        assert_close(motion.steps_per_second, 150.0);
+        // From 100 to 200 steps per second between the middles of the two halves, 100 ms apart
+        assert_close(motion.steps_per_second_squared, 1000.0);
+        assert_close(
+            motion.radians_per_second_squared,
+            1000.0 * std::f64::consts::TAU / 64.0,
+        );
+    }
+
+    #[test]
+    fn a_stopped_spinner_slows_to_zero() {
+        let motion = SpinnerMotion::estimate(&speeding_up(), 1000.0, 100.0, 64);
+
+        assert_eq!(motion, SpinnerMotion::default());
+    }
+
+    #[test]
+    fn samples_close_together_are_merged() {
+        let mut samples = Samples::default();
+        samples.record(0.0, 1);
+        samples.record(10.0, 2);
+        samples.record(11.0, 3);
+        samples.record(12.0, 4);
+
+        // 12 ms is within 4 ms of the sample at 10 ms, so it replaces the one at 11 ms
+        let positions: Vec<i64> = samples.samples.iter().map(|s| s.position).collect();
+        assert_eq!(positions, [1, 2, 4]);
+        assert_eq!(samples.at(11.5), (11.5, 2));
+    }
+
+    #[test]
+    fn reads_the_newest_samples_from_a_wrapped_ring() {
+        let mut bytes = vec![0; SAMPLE_COUNT * SAMPLE_SIZE];
+        let written = SAMPLE_COUNT as u32 + 2;
+        for index in 0..written as usize {
+            let slot = &mut bytes[(index % SAMPLE_COUNT) * SAMPLE_SIZE..][..SAMPLE_SIZE];
+            slot[0..8].copy_from_slice(&(index as f64).to_le_bytes());
+            slot[8..16].copy_from_slice(&(index as i64).to_le_bytes());
+        }
+
+        let samples = Samples::from_bytes(&bytes, written);
+        assert!(samples.wrapped);
+        assert_eq!(samples.samples.len(), SAMPLE_COUNT);
+        assert_eq!(samples.samples.front().unwrap().position, 2);
+        assert_eq!(samples.samples.back().unwrap().position, written as i64 - 1);
+    }
+
+    #[test]
+    fn full_samples_cover_the_longest_filter_window() {
+        // Every millisecond is the densest the merging allows
+        let mut samples = Samples::default();
+        for time in 0..10_000 {
+            samples.record(time as f64, time);
+        }
+
+        let oldest = samples.samples.front().unwrap().time_ms;
+        let newest = samples.samples.back().unwrap().time_ms;
+        assert!(samples.wrapped);
+        assert!(
+            newest - oldest >= MAX_FILTER_WINDOW_MS,
+            "{oldest}..{newest}"
+        );
+    }
+
+    #[test]
+    fn filter_windows_must_be_positive_and_covered_by_the_samples() {
+        for window_ms in [1.0, DEFAULT_FILTER_WINDOW_MS, MAX_FILTER_WINDOW_MS] {
+            assert!(is_valid_filter_window(window_ms), "{window_ms}");
+        }
+        for window_ms in [0.0, -1.0, 251.0, f64::INFINITY, f64::NAN] {
+            assert!(!is_valid_filter_window(window_ms), "{window_ms}");
+        }
+    }
+}
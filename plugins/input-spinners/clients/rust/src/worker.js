@@ -1,23 +1,33 @@
 /**
- * Shared memory layout (16 bytes):
+ * Shared memory layout (4136 bytes):
  * - 0: connected (u8)
- * - 1: padding
- * - 2-3: spinner1 step_delta (i16)
- * - 4-5: spinner2 step_delta (i16)
- * - 6-7: step_resolution (u16)
- * - 8-11: spinner1 angle (f32)
- * - 12-15: spinner2 angle (f32)
+ * - 1: spinner count (u8, at most 2)
+ * - 2-3: step_resolution (u16)
+ * - 4-7: padding
+ * - 8-15: spinner1 position (i64, total steps since connecting)
+ * - 16-23: spinner2 position (i64)
+ * - 24-31: padding
+ * - 32-35: spinner1 samples written (u32)
+ * - 36-39: spinner2 samples written (u32)
+ * - 40-2087: spinner1 samples, a ring of 128 x (time f64, position i64)
+ * - 2088-4135: spinner2 samples
+ *
+ * Sample times are milliseconds since the epoch (performance.timeOrigin + performance.now()).
  */
 
 const CONNECTED = 0;
-const SPINNER1_DELTA = 2;
-const SPINNER2_DELTA = 4;
-const STEP_RES = 6;
-const SPINNER1_ANGLE = 8;
-const SPINNER2_ANGLE = 12;
-const MAX_DELTA = 1000;
-
-let stepResolution = 64;
+const SPINNER_COUNT = 1;
+const STEP_RES = 2;
+const SPINNER1_POSITION = 8;
+const SPINNER2_POSITION = 16;
+const SPINNER1_SAMPLES_WRITTEN = 32;
+const SPINNER2_SAMPLES_WRITTEN = 36;
+const SAMPLE_COUNT = 128;
+const SAMPLE_SIZE = 16;
+const SPINNER1_SAMPLES = 40;
+const SPINNER2_SAMPLES = SPINNER1_SAMPLES + SAMPLE_COUNT * SAMPLE_SIZE;
+// Samples closer than this to the one before the last replace the last one
+const SAMPLE_INTERVAL_MS = 4;
 
 function withLock(fn) {
     const l = lock();
@@ -27,39 +37,48 @@ function withLock(fn) {
     return result;
 }
 
-function clamp(val, min, max) {
-    return Math.max(min, Math.min(max, val));
+function sampleOffset(samplesOffset, index) {
+    return samplesOffset + (index % SAMPLE_COUNT) * SAMPLE_SIZE;
 }
 
-function normalizeAngle(a) {
-    return Math.atan2(Math.sin(a), Math.cos(a));
-}
+function recordSample(view, writtenOffset, samplesOffset, position) {
+    const now = performance.timeOrigin + performance.now();
+    let written = view.getUint32(writtenOffset, true);
 
-function updateSpinner(view, deltaOffset, angleOffset, delta) {
-    const currentDelta = view.getInt16(deltaOffset, true);
-    view.setInt16(deltaOffset, clamp(currentDelta + delta, -MAX_DELTA, MAX_DELTA), true);
+    if (written >= 2 && now - view.getFloat64(sampleOffset(samplesOffset, written - 2), true) < SAMPLE_INTERVAL_MS) {
+        written -= 1;
+    } else {
+        view.setUint32(writtenOffset, written + 1, true);
+    }
+
+    const offset = sampleOffset(samplesOffset, written);
+    view.setFloat64(offset, now, true);
+    view.setBigInt64(offset + 8, position, true);
+}
 
-    const currentAngle = view.getFloat32(angleOffset, true);
-    const newAngle = normalizeAngle(currentAngle + (delta / stepResolution) * 2 * Math.PI);
-    view.setFloat32(angleOffset, newAngle, true);
+function updateSpinner(view, positionOffset, writtenOffset, samplesOffset, delta) {
+    const position = view.getBigInt64(positionOffset, true) + BigInt(delta);
+    view.setBigInt64(positionOffset, position, true);
+    recordSample(view, writtenOffset, samplesOffset, position);
 }
 
 function handleMessage(data) {
     if (data.type === "spinners") {
         const { spinner1_step_delta, spinner2_step_delta } = data;
         withLock((view) => {
-            if (spinner1_step_delta !== 0) updateSpinner(view, SPINNER1_DELTA, SPINNER1_ANGLE, spinner1_step_delta);
-            if (spinner2_step_delta !== 0) updateSpinner(view, SPINNER2_DELTA, SPINNER2_ANGLE, spinner2_step_delta);
+            if (spinner1_step_delta !== 0) updateSpinner(view, SPINNER1_POSITION, SPINNER1_SAMPLES_WRITTEN, SPINNER1_SAMPLES, spinner1_step_delta);
+            if (spinner2_step_delta !== 0) updateSpinner(view, SPINNER2_POSITION, SPINNER2_SAMPLES_WRITTEN, SPINNER2_SAMPLES, spinner2_step_delta);
         });
     }
 }
 
 async function init() {
     const config = await request({ type: "get_config" });
-    stepResolution = config.step_resolution;
 
     withLock((view) => {
         view.setUint8(CONNECTED, 1);
-        view.setUint16(STEP_RES, stepResolution, true);
+        // Hosts from before the spinner count have one spinner per player
+        view.setUint8(SPINNER_COUNT, Math.min(config.spinner_count ?? 2, 2));
+        view.setUint16(STEP_RES, config.step_resolution, true);
     });
 }
@@ -5,6 +5,7 @@ import HID from "node-hid";
 const VID = 0x1209;
 const PID = 0x0001;
 const STEP_RESOLUTION = 64;
+const SPINNER_COUNT = 2;
 
 const SPINNER_KEY_MAP = {
     "KeyC": { player: 1, delta: -1 },    // P1 spinner left
@@ -72,6 +73,7 @@ export default class InputSpinnersPlugin implements Plugin {
                 port.postMessage({
                     _nonce,
                     step_resolution: STEP_RESOLUTION,
+                    spinner_count: SPINNER_COUNT,
                 });
             }
         });
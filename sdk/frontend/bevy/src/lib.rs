@@ -0,0 +1,274 @@
+//! Bevy plugin for the RCade classic controls and spinners.
+//!
+//! [`RcadePlugin`] acquires both input plugins in the background and, every frame, updates:
+//!
+//! - [`ButtonInput<RcadeButton>`] with both players' buttons and the system buttons,
+//! - [`SpinnerMoved`] messages for each spinner that turned,
+//! - [`RcadeConnection`] with the status of each plugin.
+//!
+//! The raw input for the frame is kept in [`RcadeFrame`]. With [`RcadePlugin::headless`] nothing
+//! is acquired and the app writes [`RcadeFrame`] itself, which makes it possible to drive games
+//! from an in-memory host, e.g. under `MinimalPlugins` in tests.
+
+use bevy::prelude::*;
+use rcade_plugin_input_classic::ClassicController;
+use rcade_plugin_input_classic::events::Input;
+use rcade_plugin_input_classic::state::{ControllerState, Player};
+use rcade_plugin_input_spinners::{PointerConfig, SpinnerController};
+use rcade_sdk::acquire::Acquisition;
+
+pub use rcade_sdk::acquire::DeviceStatus;
+
+/// A classic control: one player's button or a system button.
+pub type RcadeButton = Input;
+
+pub struct RcadePlugin {
+    acquire: bool,
+    pointer_fallback: PointerConfig,
+}
+
+impl Default for RcadePlugin {
+    fn default() -> Self {
+        Self {
+            acquire: true,
+            pointer_fallback: PointerConfig::default(),
+        }
+    }
+}
+
+impl RcadePlugin {
+    /// Doesn't acquire any plugin. Input is read from [`RcadeFrame`], which the app is
+    /// responsible for writing before [`RcadeInputSystems`] runs.
+    pub fn headless() -> Self {
+        Self {
+            acquire: false,
+            ..Default::default()
+        }
+    }
+
+    /// Mouse and wheel mapping for the spinners when there is no RCade host.
+    pub fn with_pointer_fallback(mut self, config: PointerConfig) -> Self {
+        self.pointer_fallback = config;
+        self
+    }
+}
+
+impl Plugin for RcadePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ButtonInput<RcadeButton>>()
+            .init_resource::<RcadeFrame>()
+            .init_resource::<RcadeConnection>()
+            .add_message::<SpinnerMoved>();
+
+        if self.acquire {
+            app.insert_non_send(RcadeDevices::acquire(self.pointer_fallback))
+                .add_systems(
+                    PreUpdate,
+                    (poll_devices, update_inputs)
+                        .chain()
+                        .in_set(RcadeInputSystems),
+                );
+        } else {
+            app.add_systems(PreUpdate, update_inputs.in_set(RcadeInputSystems));
+        }
+    }
+}
+
+/// Systems that update RCade input resources and messages, in `PreUpdate`.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RcadeInputSystems;
+
+/// Raw input for the current frame.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct RcadeFrame {
+    pub classic: ControllerState,
+    /// Steps each spinner moved since the last frame, indexed by player. Reset once turned into
+    /// [`SpinnerMoved`] messages.
+    pub spinner_deltas: [i16; 2],
+    /// Angle of each spinner in radians, indexed by player.
+    pub spinner_angles: [f32; 2],
+}
+
+/// Status of each input plugin.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RcadeConnection {
+    pub classic: DeviceStatus,
+    pub spinners: DeviceStatus,
+}
+
+/// Sent for each spinner that moved during the frame.
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct SpinnerMoved {
+    pub player: Player,
+    /// Steps since the last frame.
+    pub delta: i16,
+    /// Angle in radians after the move.
+    pub angle: f32,
+}
+
+/// The acquired controllers. They hold JS objects, so they live in a non-send resource.
+struct RcadeDevices {
+    classic: Acquisition<ClassicController>,
+    spinners: Acquisition<SpinnerController>,
+}
+
+impl RcadeDevices {
+    fn acquire(pointer_fallback: PointerConfig) -> Self {
+        Self {
+            classic: Acquisition::spawn("@rcade/input-classic", ClassicController::acquire()),
+            spinners: Acquisition::spawn(
+                "@rcade/input-spinners",
+                SpinnerController::acquire_with_fallback(pointer_fallback),
+            ),
+        }
+    }
+}
+
+fn poll_devices(
+    mut devices: NonSendMut<RcadeDevices>,
+    mut frame: ResMut<RcadeFrame>,
+    mut connection: ResMut<RcadeConnection>,
+) {
+    if let Some(classic) = devices.classic.poll() {
+        frame.classic = classic.poll();
+    }
+    connection.classic = devices.classic.status();
+
+    if let Some(spinners) = devices.spinners.poll() {
+        let state = spinners.snapshot();
+        let deltas = [state.player1_delta, state.player2_delta];
+        for (total, delta) in frame.spinner_deltas.iter_mut().zip(deltas) {
+            *total = total.saturating_add(delta);
+        }
+        frame.spinner_angles = [state.player1_angle, state.player2_angle];
+    }
+    connection.spinners = devices.spinners.status();
+}
+
+fn update_inputs(
+    mut frame: ResMut<RcadeFrame>,
+    mut buttons: ResMut<ButtonInput<RcadeButton>>,
+    mut spinner_moved: MessageWriter<SpinnerMoved>,
+) {
+    // Forgetting last frame's presses alone shouldn't count as a change
+    buttons.bypass_change_detection().clear();
+    for input in Input::all() {
+        let pressed = frame.classic.is_input_pressed(input);
+        if pressed && !buttons.pressed(input) {
+            buttons.press(input);
+        } else if !pressed && buttons.pressed(input) {
+            buttons.release(input);
+        }
+    }
+
+    for player in Player::ALL {
+        let delta = frame.spinner_deltas[player.index()];
+        if delta != 0 {
+            spinner_moved.write(SpinnerMoved {
+                player,
+                delta,
+                angle: frame.spinner_angles[player.index()],
+            });
+        }
+    }
+    if frame.spinner_deltas != [0; 2] {
+        frame.spinner_deltas = [0; 2];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rcade_plugin_input_classic::state::{Button, SystemButton};
+
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, RcadePlugin::headless()));
+        app
+    }
+
+    /// Runs one frame with `classic` as the raw input.
+    fn update(app: &mut App, classic: ControllerState) {
+        app.world_mut().resource_mut::<RcadeFrame>().classic = classic;
+        app.update();
+    }
+
+    fn holding(input: Input) -> ControllerState {
+        let mut state = ControllerState::default();
+        state.set_input_pressed(input, true);
+        state
+    }
+
+    fn spinner_moves(app: &mut App) -> Vec<SpinnerMoved> {
+        app.world_mut()
+            .resource_mut::<Messages<SpinnerMoved>>()
+            .drain()
+            .collect()
+    }
+
+    #[test]
+    fn buttons_are_pressed_held_and_released() {
+        let mut app = app();
+        let a = Input::Button(Player::One, Button::A);
+
+        update(&mut app, holding(a));
+        let buttons = app.world().resource::<ButtonInput<RcadeButton>>();
+        assert!(buttons.just_pressed(a));
+        assert!(buttons.pressed(a));
+
+        update(&mut app, holding(a));
+        let buttons = app.world().resource::<ButtonInput<RcadeButton>>();
+        assert!(!buttons.just_pressed(a));
+        assert!(buttons.pressed(a));
+
+        update(&mut app, ControllerState::default());
+        let buttons = app.world().resource::<ButtonInput<RcadeButton>>();
+        assert!(buttons.just_released(a));
+        assert!(!buttons.pressed(a));
+
+        update(&mut app, ControllerState::default());
+        let buttons = app.world().resource::<ButtonInput<RcadeButton>>();
+        assert!(!buttons.just_released(a));
+    }
+
+    #[test]
+    fn system_buttons_are_inputs_too() {
+        let mut app = app();
+        let start = Input::System(SystemButton::OnePlayer);
+
+        update(&mut app, holding(start));
+        let buttons = app.world().resource::<ButtonInput<RcadeButton>>();
+        assert!(buttons.just_pressed(start));
+    }
+
+    #[test]
+    fn spinner_moved_is_sent_only_for_spinners_that_turned() {
+        let mut app = app();
+        let mut frame = app.world_mut().resource_mut::<RcadeFrame>();
+        frame.spinner_deltas = [0, -3];
+        frame.spinner_angles = [0.5, 1.5];
+        app.update();
+
+        assert_eq!(
+            spinner_moves(&mut app),
+            [SpinnerMoved {
+                player: Player::Two,
+                delta: -3,
+                angle: 1.5,
+            }]
+        );
+    }
+
+    #[test]
+    fn spinner_deltas_are_reset_after_each_frame() {
+        let mut app = app();
+        app.world_mut().resource_mut::<RcadeFrame>().spinner_deltas = [2, 4];
+        app.update();
+        assert_eq!(spinner_moves(&mut app).len(), 2);
+        assert_eq!(app.world().resource::<RcadeFrame>().spinner_deltas, [0; 2]);
+
+        app.update();
+        assert!(spinner_moves(&mut app).is_empty());
+    }
+}
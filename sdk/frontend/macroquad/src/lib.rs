@@ -0,0 +1,239 @@
+//! Macroquad input helpers for the RCade classic controls and spinners.
+//!
+//! Call [`start_rcade`] once after the window opens. Both input plugins are acquired in the
+//! background, and from then on the helpers below work like macroquad's own `is_key_down`,
+//! `is_key_pressed` and `mouse_wheel`:
+//!
+//! ```ignore
+//! use macroquad::prelude::*;
+//! use macroquad_rcade::*;
+//!
+//! #[macroquad::main("Game")]
+//! async fn main() {
+//!     start_rcade();
+//!     loop {
+//!         if is_rcade_button_pressed(RcadeButton::Button(Player::One, Button::A)) {
+//!             // jump
+//!         }
+//!         let turn = rcade_spinner_delta(Player::One);
+//!         next_frame().await
+//!     }
+//! }
+//! ```
+//!
+//! Input is sampled once per frame, after the game's own frame code, the same way macroquad
+//! collects key events between frames. Until a plugin has been acquired its buttons read as
+//! released and its spinners as still.
+//!
+//! The RCade clients talk to the host through `wasm-bindgen`, so the game has to be built with
+//! `wasm-bindgen` rather than with macroquad's default JS loader.
+
+use std::cell::RefCell;
+
+use macroquad::experimental::coroutines::start_coroutine;
+use macroquad::window::next_frame;
+use rcade_plugin_input_classic::ClassicController;
+use rcade_plugin_input_classic::state::ControllerState;
+use rcade_plugin_input_spinners::SpinnerController;
+use rcade_sdk::acquire::Acquisition;
+
+pub use rcade_plugin_input_classic::events::Input as RcadeButton;
+pub use rcade_plugin_input_classic::state::{Button, Player, SystemButton};
+pub use rcade_plugin_input_spinners::{PointerConfig, PointerSource};
+pub use rcade_sdk::acquire::DeviceStatus;
+
+/// Status of each input plugin. Both are [`DeviceStatus::Acquiring`] until [`start_rcade`] is
+/// called.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RcadeStatus {
+    pub classic: DeviceStatus,
+    pub spinners: DeviceStatus,
+}
+
+/// The input the helpers read, as sampled at the end of the last frame.
+#[derive(Default)]
+struct Sample {
+    state: ControllerState,
+    previous: ControllerState,
+    spinner_deltas: [i16; 2],
+    spinner_angles: [f32; 2],
+}
+
+impl Sample {
+    fn is_down(&self, button: RcadeButton) -> bool {
+        self.state.is_input_pressed(button)
+    }
+
+    fn is_pressed(&self, button: RcadeButton) -> bool {
+        self.state.is_input_pressed(button) && !self.previous.is_input_pressed(button)
+    }
+
+    fn is_released(&self, button: RcadeButton) -> bool {
+        !self.state.is_input_pressed(button) && self.previous.is_input_pressed(button)
+    }
+}
+
+struct Rcade {
+    classic: Acquisition<ClassicController>,
+    spinners: Acquisition<SpinnerController>,
+    status: RcadeStatus,
+    sample: Sample,
+}
+
+impl Rcade {
+    fn update(&mut self) {
+        let sample = &mut self.sample;
+        sample.previous = sample.state;
+
+        if let Some(classic) = self.classic.poll() {
+            sample.state = classic.poll();
+        }
+        self.status.classic = self.classic.status();
+
+        if let Some(spinners) = self.spinners.poll() {
+            let state = spinners.snapshot();
+            sample.spinner_deltas = [state.player1_delta, state.player2_delta];
+            sample.spinner_angles = [state.player1_angle, state.player2_angle];
+        }
+        self.status.spinners = self.spinners.status();
+    }
+}
+
+thread_local! {
+    static RCADE: RefCell<Option<Rcade>> = const { RefCell::new(None) };
+}
+
+fn with_sample<R>(default: R, f: impl FnOnce(&Sample) -> R) -> R {
+    RCADE.with_borrow(|rcade| rcade.as_ref().map_or(default, |rcade| f(&rcade.sample)))
+}
+
+/// Starts acquiring the classic controls and spinners, using the default mouse and wheel
+/// mapping for the spinners when there is no RCade host. Calling it again does nothing.
+pub fn start_rcade() {
+    start_rcade_with_pointer_fallback(PointerConfig::default());
+}
+
+/// Like [`start_rcade`], with the given mouse and wheel mapping for the spinners.
+pub fn start_rcade_with_pointer_fallback(pointer_fallback: PointerConfig) {
+    let started = RCADE.with_borrow(Option::is_some);
+    if started {
+        return;
+    }
+
+    RCADE.set(Some(Rcade {
+        classic: Acquisition::spawn("@rcade/input-classic", ClassicController::acquire()),
+        spinners: Acquisition::spawn(
+            "@rcade/input-spinners",
+            SpinnerController::acquire_with_fallback(pointer_fallback),
+        ),
+        status: RcadeStatus::default(),
+        sample: Sample::default(),
+    }));
+
+    start_coroutine(async {
+        loop {
+            RCADE.with_borrow_mut(|rcade| {
+                if let Some(rcade) = rcade {
+                    rcade.update();
+                }
+            });
+            next_frame().await;
+        }
+    });
+}
+
+pub fn rcade_status() -> RcadeStatus {
+    RCADE.with_borrow(|rcade| {
+        rcade
+            .as_ref()
+            .map_or(RcadeStatus::default(), |rcade| rcade.status)
+    })
+}
+
+/// The full state of the classic controls this frame.
+pub fn rcade_state() -> ControllerState {
+    with_sample(ControllerState::default(), |sample| sample.state)
+}
+
+/// Detect if the button is being pressed
+pub fn is_rcade_button_down(button: RcadeButton) -> bool {
+    with_sample(false, |sample| sample.is_down(button))
+}
+
+/// Detect if the button has been pressed once
+pub fn is_rcade_button_pressed(button: RcadeButton) -> bool {
+    with_sample(false, |sample| sample.is_pressed(button))
+}
+
+/// Detect if the button has been released this frame
+pub fn is_rcade_button_released(button: RcadeButton) -> bool {
+    with_sample(false, |sample| sample.is_released(button))
+}
+
+/// Steps the player's spinner moved since the last frame.
+pub fn rcade_spinner_delta(player: Player) -> i16 {
+    with_sample(0, |sample| sample.spinner_deltas[player.index()])
+}
+
+/// Angle of the player's spinner in radians, normalized to [-π, π].
+pub fn rcade_spinner_angle(player: Player) -> f32 {
+    with_sample(0.0, |sample| sample.spinner_angles[player.index()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const P1_A: RcadeButton = RcadeButton::Button(Player::One, Button::A);
+
+    /// Samples one frame per entry of `held` (whether A is held), returning whether A read as
+    /// down, pressed and released each frame.
+    fn run(held: &[bool]) -> Vec<(bool, bool, bool)> {
+        let mut sample = Sample::default();
+        held.iter()
+            .map(|held| {
+                sample.previous = sample.state;
+                sample.state.set_input_pressed(P1_A, *held);
+                (
+                    sample.is_down(P1_A),
+                    sample.is_pressed(P1_A),
+                    sample.is_released(P1_A),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pressed_and_released_last_one_frame() {
+        assert_eq!(
+            run(&[true, true, false, false]),
+            [
+                (true, true, false),
+                (true, false, false),
+                (false, false, true),
+                (false, false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn other_buttons_are_unaffected() {
+        let mut sample = Sample::default();
+        sample.state.set_input_pressed(P1_A, true);
+
+        let p2_a = RcadeButton::Button(Player::Two, Button::A);
+        assert!(!sample.is_down(p2_a) && !sample.is_pressed(p2_a));
+    }
+
+    #[test]
+    fn everything_reads_as_idle_before_start() {
+        assert_eq!(rcade_status(), RcadeStatus::default());
+        assert_eq!(rcade_status().classic, DeviceStatus::Acquiring);
+        assert_eq!(rcade_state(), ControllerState::default());
+        assert!(!is_rcade_button_down(P1_A));
+        assert!(!is_rcade_button_pressed(P1_A));
+        assert!(!is_rcade_button_released(P1_A));
+        assert_eq!(rcade_spinner_delta(Player::Two), 0);
+        assert_eq!(rcade_spinner_angle(Player::Two), 0.0);
+    }
+}
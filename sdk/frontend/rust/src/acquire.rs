@@ -0,0 +1,182 @@
+//! Acquiring input plugins in the background from a game loop.
+//!
+//! Plugin clients are acquired with an async function, but most game loops poll once per frame
+//! and can't await. [`Acquisition`] runs the future on the browser's event loop and hands the
+//! device over to the first poll after it finishes, tracking a [`DeviceStatus`] meanwhile.
+
+extern crate alloc;
+
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use core::cell::RefCell;
+use core::future::Future;
+
+use wasm_bindgen::JsValue;
+
+use crate::device::InputDevice;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DeviceStatus {
+    /// Still waiting for the host to answer.
+    #[default]
+    Acquiring,
+    /// Input comes from the cabinet.
+    Connected,
+    /// The host answered, but the hardware is not connected.
+    Disconnected,
+    /// No host answered; input comes from the keyboard, gamepads or mouse instead.
+    Fallback,
+    /// Acquiring the plugin failed.
+    Failed,
+}
+
+type Slot<T> = Rc<RefCell<Option<Result<T, JsValue>>>>;
+
+enum State<T> {
+    Pending(Slot<T>),
+    Ready(T),
+    Failed,
+}
+
+/// A device being acquired in the background, and then the device itself.
+pub struct Acquisition<T> {
+    /// Plugin name, for the error logged if acquiring fails.
+    name: String,
+    state: State<T>,
+}
+
+impl<T: InputDevice + 'static> Acquisition<T> {
+    /// Starts acquiring the plugin `name` with `future`, e.g. `ClassicController::acquire()`.
+    pub fn spawn(name: &str, future: impl Future<Output = Result<T, JsValue>> + 'static) -> Self {
+        let slot: Slot<T> = Rc::default();
+        let target = slot.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            *target.borrow_mut() = Some(future.await);
+        });
+
+        Self {
+            name: name.into(),
+            state: State::Pending(slot),
+        }
+    }
+
+    /// Moves a finished acquisition into place, returning the device once it is ready. Failures
+    /// are logged to the console.
+    pub fn poll(&mut self) -> Option<&T> {
+        if let State::Pending(slot) = &self.state {
+            let result = slot.borrow_mut().take();
+            match result {
+                None => {}
+                Some(Ok(device)) => self.state = State::Ready(device),
+                Some(Err(err)) => {
+                    web_sys::console::error_2(
+                        &format!("failed to acquire {}", self.name).into(),
+                        &err,
+                    );
+                    self.state = State::Failed;
+                }
+            }
+        }
+
+        match &self.state {
+            State::Ready(device) => Some(device),
+            _ => None,
+        }
+    }
+
+    /// Status as of the last [`Acquisition::poll`].
+    pub fn status(&self) -> DeviceStatus {
+        match &self.state {
+            State::Pending(_) => DeviceStatus::Acquiring,
+            State::Failed => DeviceStatus::Failed,
+            State::Ready(device) if device.is_fallback() => DeviceStatus::Fallback,
+            State::Ready(device) if device.connected() => DeviceStatus::Connected,
+            State::Ready(_) => DeviceStatus::Disconnected,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::Capabilities;
+    use crate::replay::test_frames::Byte;
+
+    struct Device {
+        connected: bool,
+        fallback: bool,
+    }
+
+    impl InputDevice for Device {
+        type Frame = Byte;
+
+        fn connected(&self) -> bool {
+            self.connected
+        }
+
+        fn is_fallback(&self) -> bool {
+            self.fallback
+        }
+
+        fn player_count(&self) -> u8 {
+            2
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities::default()
+        }
+
+        fn update(&mut self) -> Byte {
+            Byte(0)
+        }
+    }
+
+    fn ready(connected: bool, fallback: bool) -> Acquisition<Device> {
+        Acquisition {
+            name: "test".into(),
+            state: State::Ready(Device {
+                connected,
+                fallback,
+            }),
+        }
+    }
+
+    #[test]
+    fn status_follows_the_device() {
+        assert_eq!(ready(true, false).status(), DeviceStatus::Connected);
+        assert_eq!(ready(false, false).status(), DeviceStatus::Disconnected);
+        // A fallback device reports connected, but isn't the cabinet.
+        assert_eq!(ready(true, true).status(), DeviceStatus::Fallback);
+    }
+
+    #[test]
+    fn failures_are_reported() {
+        let acquisition = Acquisition::<Device> {
+            name: "test".into(),
+            state: State::Failed,
+        };
+        assert_eq!(acquisition.status(), DeviceStatus::Failed);
+    }
+
+    #[test]
+    fn the_device_is_handed_over_on_the_first_poll_after_acquiring() {
+        let slot: Slot<Device> = Rc::default();
+        let mut acquisition = Acquisition {
+            name: "test".into(),
+            state: State::Pending(slot.clone()),
+        };
+
+        assert!(acquisition.poll().is_none());
+        assert_eq!(acquisition.status(), DeviceStatus::Acquiring);
+
+        *slot.borrow_mut() = Some(Ok(Device {
+            connected: true,
+            fallback: false,
+        }));
+        assert_eq!(acquisition.status(), DeviceStatus::Acquiring);
+        assert!(acquisition.poll().is_some_and(|device| device.connected));
+        assert_eq!(acquisition.status(), DeviceStatus::Connected);
+        assert!(acquisition.poll().is_some());
+    }
+}
@@ -11,6 +11,8 @@ use wasm_bindgen_futures::JsFuture;
 use web_sys::DedicatedWorkerGlobalScope;
 use web_sys::{MessageEvent, MessagePort};
 
+type MessageClosure = Closure<dyn FnMut(MessageEvent)>;
+
 fn generate_nonce() -> String {
     use js_sys::Math;
     format!(
@@ -20,6 +22,20 @@ fn generate_nonce() -> String {
     )
 }
 
+/// Whether a host could answer plugin requests.
+///
+/// Games are embedded in the host's page, so a top-level window (for example a plain dev server
+/// opened in a browser) has no host. Workers always report `true`, as they ask their parent.
+pub fn host_available() -> bool {
+    match web_sys::window() {
+        Some(window) => match window.parent() {
+            Ok(Some(parent)) => !js_sys::Object::is(&parent, &window),
+            _ => false,
+        },
+        None => true,
+    }
+}
+
 pub struct PluginChannel {
     port: MessagePort,
 }
@@ -30,15 +46,34 @@ impl PluginChannel {
     }
 
     pub async fn acquire(name: &str, version: &str) -> Result<Self, JsValue> {
+        let result = JsFuture::from(Self::request_channel(name, version)).await?;
+
+        Self::from_resolved(result)
+    }
+
+    /// Like [`PluginChannel::acquire`], but resolves to `None` right away when there is no host
+    /// (see [`host_available`]), so the caller can fall back to other input.
+    ///
+    /// Inside the host's page there is no timeout: this waits for the host to answer, however long
+    /// that takes, and never falls back.
+    pub async fn acquire_if_hosted(name: &str, version: &str) -> Result<Option<Self>, JsValue> {
+        if !host_available() {
+            return Ok(None);
+        }
+
+        Self::acquire(name, version).await.map(Some)
+    }
+
+    /// Asks the host for a channel, returning a promise that resolves once it answers
+    fn request_channel(name: &str, version: &str) -> js_sys::Promise {
         let nonce = generate_nonce();
 
         // Create a JS Promise
-        let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        js_sys::Promise::new(&mut |resolve, reject| {
             let nonce_for_closure = nonce.clone();
 
             // Store the closure in an Rc<RefCell> so we can reference it from within
-            let closure_holder: Rc<RefCell<Option<Closure<dyn FnMut(MessageEvent)>>>> =
-                Rc::new(RefCell::new(None));
+            let closure_holder: Rc<RefCell<Option<MessageClosure>>> = Rc::new(RefCell::new(None));
             let closure_holder_clone = closure_holder.clone();
 
             // Create a closure that will handle the message
@@ -53,96 +88,88 @@ impl PluginChannel {
                     if let (Some(type_str), Some(recv_nonce)) = (
                         type_val.and_then(|v| v.as_string()),
                         nonce_val.and_then(|v| v.as_string()),
-                    ) {
-                        if type_str == "plugin_channel" && recv_nonce == nonce_for_closure {
-                            let ports = event.ports();
-                            let port = if ports.length() > 0 {
-                                ports.get(0).dyn_into::<MessagePort>().ok()
-                            } else {
-                                None
-                            };
-
-                            // Check for error response
-                            if let Ok(error_val) =
-                                js_sys::Reflect::get(&obj, &JsValue::from_str("error"))
-                            {
-                                if !error_val.is_undefined() {
-                                    // Remove the event listener
-                                    if let Some(closure_ref) =
-                                        closure_holder_clone.borrow().as_ref()
-                                    {
-                                        let target = event.target();
-                                        if let Some(window) = target
-                                            .clone()
-                                            .and_then(|t| t.dyn_into::<web_sys::Window>().ok())
-                                        {
-                                            let _ = window.remove_event_listener_with_callback(
-                                                "message",
-                                                closure_ref.as_ref().unchecked_ref(),
-                                            );
-                                        } else if let Some(worker) = target.and_then(|t| {
-                                            t.dyn_into::<DedicatedWorkerGlobalScope>().ok()
-                                        }) {
-                                            let _ = worker.remove_event_listener_with_callback(
-                                                "message",
-                                                closure_ref.as_ref().unchecked_ref(),
-                                            );
-                                        }
-                                    }
+                    ) && type_str == "plugin_channel"
+                        && recv_nonce == nonce_for_closure
+                    {
+                        let ports = event.ports();
+                        let port = if ports.length() > 0 {
+                            ports.get(0).dyn_into::<MessagePort>().ok()
+                        } else {
+                            None
+                        };
 
-                                    // Reject with the error
-                                    let error_msg = error_val
-                                        .as_string()
-                                        .unwrap_or_else(|| "Unknown error".to_string());
-                                    let _ = reject
-                                        .call1(&JsValue::NULL, &JsValue::from_str(&error_msg));
-                                    return;
+                        // Check for error response
+                        if let Ok(error_val) =
+                            js_sys::Reflect::get(&obj, &JsValue::from_str("error"))
+                            && !error_val.is_undefined()
+                        {
+                            // Remove the event listener
+                            if let Some(closure_ref) = closure_holder_clone.borrow().as_ref() {
+                                let target = event.target();
+                                if let Some(window) = target
+                                    .clone()
+                                    .and_then(|t| t.dyn_into::<web_sys::Window>().ok())
+                                {
+                                    let _ = window.remove_event_listener_with_callback(
+                                        "message",
+                                        closure_ref.as_ref().unchecked_ref(),
+                                    );
+                                } else if let Some(worker) = target
+                                    .and_then(|t| t.dyn_into::<DedicatedWorkerGlobalScope>().ok())
+                                {
+                                    let _ = worker.remove_event_listener_with_callback(
+                                        "message",
+                                        closure_ref.as_ref().unchecked_ref(),
+                                    );
                                 }
                             }
 
-                            let channel_obj =
-                                js_sys::Reflect::get(&obj, &JsValue::from_str("channel")).ok();
-
-                            if let Some(channel) = channel_obj {
-                                let name =
-                                    js_sys::Reflect::get(&channel, &JsValue::from_str("name"))
-                                        .ok()
-                                        .and_then(|v| v.as_string());
-                                let version =
-                                    js_sys::Reflect::get(&channel, &JsValue::from_str("version"))
-                                        .ok()
-                                        .and_then(|v| v.as_string());
-
-                                if let (Some(_name), Some(_version), Some(port)) =
-                                    (name, version, port)
-                                {
-                                    // Remove the event listener
-                                    if let Some(closure_ref) =
-                                        closure_holder_clone.borrow().as_ref()
+                            // Reject with the error
+                            let error_msg = error_val
+                                .as_string()
+                                .unwrap_or_else(|| "Unknown error".to_string());
+                            let _ = reject.call1(&JsValue::NULL, &JsValue::from_str(&error_msg));
+                            return;
+                        }
+
+                        let channel_obj =
+                            js_sys::Reflect::get(&obj, &JsValue::from_str("channel")).ok();
+
+                        if let Some(channel) = channel_obj {
+                            let name = js_sys::Reflect::get(&channel, &JsValue::from_str("name"))
+                                .ok()
+                                .and_then(|v| v.as_string());
+                            let version =
+                                js_sys::Reflect::get(&channel, &JsValue::from_str("version"))
+                                    .ok()
+                                    .and_then(|v| v.as_string());
+
+                            if let (Some(_name), Some(_version), Some(port)) = (name, version, port)
+                            {
+                                // Remove the event listener
+                                if let Some(closure_ref) = closure_holder_clone.borrow().as_ref() {
+                                    let target = event.target();
+                                    if let Some(window) = target
+                                        .clone()
+                                        .and_then(|t| t.dyn_into::<web_sys::Window>().ok())
                                     {
-                                        let target = event.target();
-                                        if let Some(window) = target
-                                            .clone()
-                                            .and_then(|t| t.dyn_into::<web_sys::Window>().ok())
-                                        {
-                                            let _ = window.remove_event_listener_with_callback(
-                                                "message",
-                                                closure_ref.as_ref().unchecked_ref(),
-                                            );
-                                        } else if let Some(worker) = target.and_then(|t| {
-                                            t.dyn_into::<DedicatedWorkerGlobalScope>().ok()
-                                        }) {
-                                            let _ = worker.remove_event_listener_with_callback(
-                                                "message",
-                                                closure_ref.as_ref().unchecked_ref(),
-                                            );
-                                        }
+                                        let _ = window.remove_event_listener_with_callback(
+                                            "message",
+                                            closure_ref.as_ref().unchecked_ref(),
+                                        );
+                                    } else if let Some(worker) = target.and_then(|t| {
+                                        t.dyn_into::<DedicatedWorkerGlobalScope>().ok()
+                                    }) {
+                                        let _ = worker.remove_event_listener_with_callback(
+                                            "message",
+                                            closure_ref.as_ref().unchecked_ref(),
+                                        );
                                     }
-
-                                    // Resolve with the channel
-                                    let channel = PluginChannel::new(port);
-                                    let _ = resolve.call1(&JsValue::NULL, &JsValue::from(channel));
                                 }
+
+                                // Resolve with the channel
+                                let channel = PluginChannel::new(port);
+                                let _ = resolve.call1(&JsValue::NULL, &JsValue::from(channel));
                             }
                         }
                     }
@@ -203,11 +230,10 @@ impl PluginChannel {
             } else if let Ok(worker) = js_sys::global().dyn_into::<DedicatedWorkerGlobalScope>() {
                 let _ = worker.post_message(&message);
             }
-        });
-
-        // Await the promise
-        let result = JsFuture::from(promise).await?;
+        })
+    }
 
+    fn from_resolved(result: JsValue) -> Result<Self, JsValue> {
         // Extract the MessagePort from the resolved value
         let obj = result.dyn_into::<js_sys::Object>()?;
         let port =
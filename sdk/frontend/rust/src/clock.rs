@@ -0,0 +1,12 @@
+//! The clock shared by plugin clients and their workers.
+
+/// Milliseconds since the epoch, as `performance.timeOrigin + performance.now()`.
+///
+/// Plugin workers timestamp input with the same expression, so these times can be compared with
+/// theirs. Falls back to `Date.now()` where there is no `performance` object.
+pub fn now_ms() -> f64 {
+    match web_sys::window().and_then(|window| window.performance()) {
+        Some(performance) => performance.time_origin() + performance.now(),
+        None => js_sys::Date::now(),
+    }
+}
@@ -0,0 +1,77 @@
+//! A common interface to RCade input plugins.
+//!
+//! Every plugin client implements [`InputDevice`], so code that only needs "the input for this
+//! frame" (recording, remapping, debug overlays, ...) can be written once for any plugin. Devices
+//! can be paired with a tuple to treat several plugins as one.
+
+use crate::replay::Frame;
+
+/// What a device offers each player, and in total.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Capabilities {
+    /// Digital buttons per player, d-pad directions included.
+    pub buttons_per_player: u8,
+    /// Buttons shared by all players, such as the player-select buttons.
+    pub system_buttons: u8,
+    /// Spinners per player.
+    pub spinners_per_player: u8,
+}
+
+impl Capabilities {
+    /// Everything offered by two devices used together.
+    pub fn combined(self, other: Capabilities) -> Capabilities {
+        Capabilities {
+            buttons_per_player: self.buttons_per_player + other.buttons_per_player,
+            system_buttons: self.system_buttons + other.system_buttons,
+            spinners_per_player: self.spinners_per_player + other.spinners_per_player,
+        }
+    }
+}
+
+/// An input plugin (or anything standing in for one) that produces one [`Frame`] of input per
+/// game frame.
+pub trait InputDevice {
+    type Frame: Frame;
+
+    /// Whether input is currently available, e.g. the cabinet hardware is plugged in.
+    fn connected(&self) -> bool;
+
+    /// Whether input comes from something standing in for the cabinet, such as the keyboard when
+    /// there is no RCade host.
+    fn is_fallback(&self) -> bool {
+        false
+    }
+
+    /// Number of players the device has inputs for.
+    fn player_count(&self) -> u8;
+
+    fn capabilities(&self) -> Capabilities;
+
+    /// Reads the input for the current frame. Call exactly once per frame.
+    fn update(&mut self) -> Self::Frame;
+}
+
+/// Connected while both devices are, with the players and capabilities of both.
+impl<A: InputDevice, B: InputDevice> InputDevice for (A, B) {
+    type Frame = (A::Frame, B::Frame);
+
+    fn connected(&self) -> bool {
+        self.0.connected() && self.1.connected()
+    }
+
+    fn is_fallback(&self) -> bool {
+        self.0.is_fallback() || self.1.is_fallback()
+    }
+
+    fn player_count(&self) -> u8 {
+        self.0.player_count().max(self.1.player_count())
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.0.capabilities().combined(self.1.capabilities())
+    }
+
+    fn update(&mut self) -> Self::Frame {
+        (self.0.update(), self.1.update())
+    }
+}
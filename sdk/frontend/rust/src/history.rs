@@ -0,0 +1,266 @@
+//! Frame-indexed input history for deterministic simulation and rollback.
+//!
+//! [`InputHistory`] keeps the last few hundred frames of any [`Frame`] type, e.g. a classic
+//! `ControllerState`, spinner deltas, or both as a tuple. Frames whose input isn't known yet can
+//! be predicted by repeating the last known input, and corrected once the real input arrives;
+//! the history then reports the earliest frame that has to be simulated again.
+
+use std::collections::VecDeque;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::replay::Frame;
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Entry<F> {
+    input: F,
+    predicted: bool,
+}
+
+/// A ring buffer of inputs indexed by frame number.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InputHistory<F> {
+    entries: VecDeque<Entry<F>>,
+    /// Frame number of the first entry.
+    start: u64,
+    capacity: usize,
+    /// Earliest frame whose input changed after it may already have been used.
+    rollback: Option<u64>,
+}
+
+impl<F: Frame + PartialEq> InputHistory<F> {
+    /// Keeps the latest `capacity` frames (at least one).
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity.max(1)),
+            start: 0,
+            capacity: capacity.max(1),
+            rollback: None,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The oldest frame still in the history.
+    pub fn start_frame(&self) -> u64 {
+        self.start
+    }
+
+    /// The frame after the newest one in the history.
+    pub fn end_frame(&self) -> u64 {
+        self.start + self.entries.len() as u64
+    }
+
+    /// The input stored for `frame`, confirmed or predicted. `None` for frames that have dropped
+    /// out of the history or haven't been reached yet.
+    pub fn input_at(&self, frame: u64) -> Option<&F> {
+        self.entry(frame).map(|entry| &entry.input)
+    }
+
+    /// Whether the input stored for `frame` is a prediction rather than confirmed input.
+    pub fn is_predicted(&self, frame: u64) -> bool {
+        self.entry(frame).is_some_and(|entry| entry.predicted)
+    }
+
+    /// The input for `frame`: the stored one if there is one, otherwise a prediction that repeats
+    /// the latest input before it (and is stored as such).
+    pub fn predict(&mut self, frame: u64) -> F {
+        self.start_at(frame);
+        if frame < self.start {
+            return F::default();
+        }
+
+        self.extend_to(frame);
+        self.entries[(frame - self.start) as usize].input.clone()
+    }
+
+    /// Stores the real input for `frame`, replacing any prediction or earlier input.
+    ///
+    /// Returns `true` if this changed the input of a frame that had already been stored, in which
+    /// case simulation has to resume from that frame (see [`InputHistory::take_rollback`]).
+    /// Frames that have dropped out of the history are ignored.
+    pub fn confirm(&mut self, frame: u64, input: F) -> bool {
+        self.start_at(frame);
+        if frame < self.start {
+            return false;
+        }
+
+        if frame >= self.end_frame() {
+            if frame > self.start {
+                self.extend_to(frame - 1);
+            }
+            self.push(Entry {
+                input,
+                predicted: false,
+            });
+            return false;
+        }
+
+        let index = (frame - self.start) as usize;
+        let entry = &mut self.entries[index];
+        let changed = entry.input != input;
+        entry.input = input;
+        entry.predicted = false;
+
+        if changed {
+            self.repredict_after(index);
+            self.rollback = Some(self.rollback.map_or(frame, |rollback| rollback.min(frame)));
+        }
+
+        changed
+    }
+
+    /// The earliest frame whose input was corrected since the last call, if any.
+    pub fn take_rollback(&mut self) -> Option<u64> {
+        self.rollback.take()
+    }
+
+    /// Forgets every frame, starting over at `frame`.
+    pub fn reset(&mut self, frame: u64) {
+        self.entries.clear();
+        self.start = frame;
+        self.rollback = None;
+    }
+
+    /// An empty history starts at the first frame it is given.
+    fn start_at(&mut self, frame: u64) {
+        if self.entries.is_empty() {
+            self.start = frame;
+        }
+    }
+
+    fn entry(&self, frame: u64) -> Option<&Entry<F>> {
+        let index = frame.checked_sub(self.start)?;
+        self.entries.get(index as usize)
+    }
+
+    /// Predicts every missing frame up to and including `frame`.
+    fn extend_to(&mut self, frame: u64) {
+        // Frames that would drop out again right away don't need to be filled in
+        let first_kept = (frame + 1).saturating_sub(self.capacity as u64);
+        if self.end_frame() < first_kept {
+            let last = self.entries.pop_back();
+            self.entries.clear();
+            self.start = first_kept;
+            if let Some(last) = last {
+                self.entries.push_back(Entry {
+                    input: last.input,
+                    predicted: true,
+                });
+            }
+        }
+
+        while self.end_frame() <= frame {
+            let input = self
+                .entries
+                .back()
+                .map(|entry| entry.input.clone())
+                .unwrap_or_default();
+            self.push(Entry {
+                input,
+                predicted: true,
+            });
+        }
+    }
+
+    fn push(&mut self, entry: Entry<F>) {
+        self.entries.push_back(entry);
+        if self.entries.len() > self.capacity {
+            self.entries.pop_front();
+            self.start += 1;
+        }
+    }
+
+    /// Updates the predictions that followed a corrected frame.
+    fn repredict_after(&mut self, index: usize) {
+        let mut last = self.entries[index].input.clone();
+        for entry in self.entries.iter_mut().skip(index + 1) {
+            if entry.predicted {
+                entry.input = last.clone();
+            } else {
+                last = entry.input.clone();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::test_frames::Byte;
+
+    #[test]
+    fn predictions_repeat_the_last_confirmed_input() {
+        let mut history = InputHistory::new(16);
+        history.confirm(0, Byte(1));
+
+        assert_eq!(history.predict(3), Byte(1));
+        assert!(!history.is_predicted(0));
+        assert!(history.is_predicted(2));
+        assert_eq!(history.end_frame(), 4);
+    }
+
+    #[test]
+    fn confirming_a_matching_prediction_needs_no_rollback() {
+        let mut history = InputHistory::new(16);
+        history.confirm(0, Byte(1));
+        history.predict(2);
+
+        assert!(!history.confirm(1, Byte(1)));
+        assert!(!history.is_predicted(1));
+        assert_eq!(history.take_rollback(), None);
+    }
+
+    #[test]
+    fn a_wrong_prediction_is_corrected_and_later_ones_repredicted() {
+        let mut history = InputHistory::new(16);
+        history.confirm(0, Byte(1));
+        history.predict(3);
+
+        assert!(history.confirm(1, Byte(2)));
+        assert_eq!(history.input_at(2), Some(&Byte(2)));
+        assert_eq!(history.input_at(3), Some(&Byte(2)));
+        assert_eq!(history.input_at(0), Some(&Byte(1)));
+    }
+
+    #[test]
+    fn rollback_reports_the_earliest_correction_once() {
+        let mut history = InputHistory::new(16);
+        history.confirm(0, Byte(1));
+        history.predict(5);
+
+        history.confirm(3, Byte(2));
+        history.confirm(2, Byte(3));
+        history.confirm(4, Byte(4));
+        assert_eq!(history.take_rollback(), Some(2));
+        assert_eq!(history.take_rollback(), None);
+    }
+
+    #[test]
+    fn old_frames_drop_out() {
+        let mut history = InputHistory::new(4);
+        for frame in 0..6 {
+            history.confirm(frame, Byte(frame as u8));
+        }
+
+        assert_eq!(history.start_frame(), 2);
+        assert_eq!(history.input_at(1), None);
+        assert!(!history.confirm(1, Byte(9)));
+        assert_eq!(history.input_at(5), Some(&Byte(5)));
+    }
+
+    #[test]
+    fn jumping_far_ahead_keeps_only_the_capacity() {
+        let mut history = InputHistory::new(4);
+        history.confirm(0, Byte(7));
+
+        assert_eq!(history.predict(1_000_000), Byte(7));
+        assert_eq!(history.start_frame(), 999_997);
+        assert_eq!(history.end_frame(), 1_000_001);
+    }
+}
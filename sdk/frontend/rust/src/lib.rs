@@ -1,2 +1,8 @@
+pub mod acquire;
 pub mod channel;
+pub mod clock;
+pub mod device;
+pub mod history;
+pub mod player;
+pub mod replay;
 pub mod shmem_runner;
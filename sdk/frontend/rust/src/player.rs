@@ -0,0 +1,56 @@
+//! The players of an RCade cabinet, shared by every input plugin.
+
+use core::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// One side of the cabinet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Player {
+    One,
+    Two,
+}
+
+impl fmt::Display for Player {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Player::One => "P1",
+            Player::Two => "P2",
+        })
+    }
+}
+
+impl Player {
+    pub const ALL: [Player; 2] = [Player::One, Player::Two];
+
+    pub fn other(self) -> Player {
+        match self {
+            Player::One => Player::Two,
+            Player::Two => Player::One,
+        }
+    }
+
+    /// The player numbered `number` by the host (1 or 2).
+    pub fn from_number(number: u8) -> Option<Player> {
+        match number {
+            1 => Some(Player::One),
+            2 => Some(Player::Two),
+            _ => None,
+        }
+    }
+
+    /// The number the host uses for this player, starting at 1.
+    pub fn number(self) -> u8 {
+        self.index() as u8 + 1
+    }
+
+    /// Position in [`Player::ALL`], for per-player arrays.
+    pub fn index(self) -> usize {
+        match self {
+            Player::One => 0,
+            Player::Two => 1,
+        }
+    }
+}
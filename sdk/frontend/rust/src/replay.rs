@@ -0,0 +1,494 @@
+//! Frame-by-frame input recording and deterministic playback.
+//!
+//! Any [`InputDevice`] can be wrapped in a [`Recorder`] (live input, saved as it is read), and a
+//! saved [`Recording`] can be fed back frame by frame through a [`Playback`], which is itself an
+//! [`InputDevice`] describing the device that was recorded. A game reads its input the same way
+//! in both cases. Devices can be paired with a tuple to record several plugins into one
+//! recording.
+//!
+//! # Format
+//!
+//! All integers are little-endian.
+//!
+//! ```text
+//! [0-3]   magic "RCRP"
+//! [4]     format version (1)
+//! [5]     reserved (0)
+//! [6-7]   frame size in bytes (u16)
+//! [8-11]  frame count (u32)
+//! [12]    player count
+//! [13]    buttons per player
+//! [14]    system buttons
+//! [15]    spinners per player
+//! [16..]  runs of identical frames: repeat count (u16) followed by one encoded frame
+//! ```
+
+use core::fmt;
+
+use crate::device::{Capabilities, InputDevice};
+
+const MAGIC: &[u8; 4] = b"RCRP";
+const HEADER_SIZE: usize = 16;
+const RUN_SIZE: usize = 2;
+
+/// Version of the recording format written by [`Recording::to_bytes`].
+pub const FORMAT_VERSION: u8 = 1;
+
+/// A snapshot of one frame of input that can be stored in a [`Recording`].
+pub trait Frame: Clone + Default {
+    /// Encoded size in bytes, the same for every frame of this type. At most `u16::MAX`, the
+    /// largest size the header can hold.
+    const SIZE: usize;
+
+    /// Writes the frame into `out`, which is exactly [`Frame::SIZE`] bytes long.
+    fn encode(&self, out: &mut [u8]);
+
+    /// Reads a frame back from exactly [`Frame::SIZE`] bytes.
+    fn decode(bytes: &[u8]) -> Self;
+}
+
+impl<A: Frame, B: Frame> Frame for (A, B) {
+    const SIZE: usize = A::SIZE + B::SIZE;
+
+    fn encode(&self, out: &mut [u8]) {
+        let (a, b) = out.split_at_mut(A::SIZE);
+        self.0.encode(a);
+        self.1.encode(b);
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        let (a, b) = bytes.split_at(A::SIZE);
+        (A::decode(a), B::decode(b))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The data does not start with the recording magic.
+    BadMagic,
+    /// The recording was written in a format version other than [`FORMAT_VERSION`].
+    UnsupportedVersion(u8),
+    /// The recording holds frames of a different type.
+    FrameSizeMismatch { expected: usize, found: usize },
+    /// The data ends before all frames were read.
+    Truncated,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::BadMagic => write!(f, "not an RCade input recording"),
+            ReplayError::UnsupportedVersion(version) => {
+                write!(f, "unsupported recording format version {version}")
+            }
+            ReplayError::FrameSizeMismatch { expected, found } => write!(
+                f,
+                "recording frame size is {found} bytes, expected {expected}"
+            ),
+            ReplayError::Truncated => write!(f, "recording is truncated"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// An ordered list of recorded frames, along with a description of the device they came from.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Recording<F> {
+    frames: Vec<F>,
+    player_count: u8,
+    capabilities: Capabilities,
+}
+
+impl<F: Frame> Recording<F> {
+    /// An empty recording of an unknown device (no players, no capabilities).
+    pub fn new() -> Self {
+        Self::for_device(0, Capabilities::default())
+    }
+
+    /// An empty recording of a device with the given players and capabilities.
+    pub fn for_device(player_count: u8, capabilities: Capabilities) -> Self {
+        Self {
+            frames: Vec::new(),
+            player_count,
+            capabilities,
+        }
+    }
+
+    pub fn player_count(&self) -> u8 {
+        self.player_count
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    pub fn push(&mut self, frame: F) {
+        self.frames.push(frame);
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&F> {
+        self.frames.get(index)
+    }
+
+    pub fn frames(&self) -> &[F] {
+        &self.frames
+    }
+
+    /// Encodes the recording, collapsing runs of identical frames.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_SIZE);
+        out.extend_from_slice(MAGIC);
+        out.push(FORMAT_VERSION);
+        out.push(0);
+        let frame_size: u16 = const {
+            assert!(
+                F::SIZE <= u16::MAX as usize,
+                "frame too large for the header"
+            );
+            F::SIZE as u16
+        };
+        out.extend_from_slice(&frame_size.to_le_bytes());
+        out.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+        out.extend_from_slice(&[
+            self.player_count,
+            self.capabilities.buttons_per_player,
+            self.capabilities.system_buttons,
+            self.capabilities.spinners_per_player,
+        ]);
+
+        let mut current = vec![0; F::SIZE];
+        let mut next = vec![0; F::SIZE];
+        let mut run: u16 = 0;
+
+        for frame in &self.frames {
+            frame.encode(&mut next);
+
+            if run > 0 && (next != current || run == u16::MAX) {
+                out.extend_from_slice(&run.to_le_bytes());
+                out.extend_from_slice(&current);
+                run = 0;
+            }
+
+            core::mem::swap(&mut current, &mut next);
+            run += 1;
+        }
+
+        if run > 0 {
+            out.extend_from_slice(&run.to_le_bytes());
+            out.extend_from_slice(&current);
+        }
+
+        out
+    }
+
+    /// Decodes a recording written by [`Recording::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(ReplayError::BadMagic);
+        }
+
+        if bytes.len() < HEADER_SIZE {
+            return Err(ReplayError::Truncated);
+        }
+
+        let version = bytes[4];
+        if version != FORMAT_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+
+        let frame_size = u16::from_le_bytes([bytes[6], bytes[7]]) as usize;
+        if frame_size != F::SIZE {
+            return Err(ReplayError::FrameSizeMismatch {
+                expected: F::SIZE,
+                found: frame_size,
+            });
+        }
+
+        let count = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize;
+        let player_count = bytes[12];
+        let capabilities = Capabilities {
+            buttons_per_player: bytes[13],
+            system_buttons: bytes[14],
+            spinners_per_player: bytes[15],
+        };
+
+        let mut body = &bytes[HEADER_SIZE..];
+
+        // Each run holds at most u16::MAX frames, so a count the body can't hold is caught before
+        // allocating anything for it
+        let runs = body.len() / (RUN_SIZE + frame_size);
+        if count > runs.saturating_mul(u16::MAX as usize) {
+            return Err(ReplayError::Truncated);
+        }
+
+        let mut frames = Vec::with_capacity(count.min(runs));
+
+        while frames.len() < count {
+            if body.len() < RUN_SIZE + frame_size {
+                return Err(ReplayError::Truncated);
+            }
+
+            let run = u16::from_le_bytes([body[0], body[1]]) as usize;
+            let frame = F::decode(&body[RUN_SIZE..RUN_SIZE + frame_size]);
+            let run = run.min(count - frames.len());
+            frames.extend(core::iter::repeat_n(frame, run));
+
+            body = &body[RUN_SIZE + frame_size..];
+        }
+
+        Ok(Self {
+            frames,
+            player_count,
+            capabilities,
+        })
+    }
+}
+
+/// Passes input through from a live device while recording every frame read.
+pub struct Recorder<D: InputDevice> {
+    device: D,
+    recording: Recording<D::Frame>,
+}
+
+impl<D: InputDevice> Recorder<D> {
+    pub fn new(device: D) -> Self {
+        let recording = Recording::for_device(device.player_count(), device.capabilities());
+        Self { device, recording }
+    }
+
+    /// Index of the next frame to be read.
+    pub fn frame_index(&self) -> usize {
+        self.recording.len()
+    }
+
+    pub fn recording(&self) -> &Recording<D::Frame> {
+        &self.recording
+    }
+
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    /// Stops recording, giving back the device and everything recorded.
+    pub fn finish(self) -> (D, Recording<D::Frame>) {
+        (self.device, self.recording)
+    }
+}
+
+impl<D: InputDevice> InputDevice for Recorder<D> {
+    type Frame = D::Frame;
+
+    fn connected(&self) -> bool {
+        self.device.connected()
+    }
+
+    fn is_fallback(&self) -> bool {
+        self.device.is_fallback()
+    }
+
+    fn player_count(&self) -> u8 {
+        self.device.player_count()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.device.capabilities()
+    }
+
+    fn update(&mut self) -> Self::Frame {
+        let frame = self.device.update();
+        self.recording.push(frame.clone());
+        frame
+    }
+}
+
+/// Feeds a [`Recording`] back one frame per update.
+///
+/// Playback is always connected and reports the players and capabilities of the recorded device.
+/// Once the recording runs out, updates return the default (idle) frame.
+pub struct Playback<F> {
+    recording: Recording<F>,
+    index: usize,
+}
+
+impl<F: Frame> Playback<F> {
+    pub fn new(recording: Recording<F>) -> Self {
+        Self {
+            recording,
+            index: 0,
+        }
+    }
+
+    /// Index of the next frame to be read.
+    pub fn frame_index(&self) -> usize {
+        self.index
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.index >= self.recording.len()
+    }
+
+    /// Moves playback so the next update returns frame `index`.
+    pub fn seek(&mut self, index: usize) {
+        self.index = index;
+    }
+
+    pub fn rewind(&mut self) {
+        self.seek(0);
+    }
+
+    pub fn recording(&self) -> &Recording<F> {
+        &self.recording
+    }
+}
+
+impl<F: Frame> InputDevice for Playback<F> {
+    type Frame = F;
+
+    fn connected(&self) -> bool {
+        true
+    }
+
+    fn player_count(&self) -> u8 {
+        self.recording.player_count()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.recording.capabilities()
+    }
+
+    fn update(&mut self) -> F {
+        let frame = self.recording.get(self.index).cloned().unwrap_or_default();
+        self.index += 1;
+        frame
+    }
+}
+
+/// Frames for tests of this module and of [`crate::history`].
+#[cfg(test)]
+pub(crate) mod test_frames {
+    use super::Frame;
+
+    /// A one-byte frame.
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    pub(crate) struct Byte(pub(crate) u8);
+
+    impl Frame for Byte {
+        const SIZE: usize = 1;
+
+        fn encode(&self, out: &mut [u8]) {
+            out[0] = self.0;
+        }
+
+        fn decode(bytes: &[u8]) -> Self {
+            Byte(bytes[0])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_frames::Byte;
+    use super::*;
+
+    #[test]
+    fn round_trips_runs_of_frames() {
+        let mut recording = Recording::for_device(2, Capabilities::default());
+        for byte in [1, 1, 1, 2, 0, 0] {
+            recording.push(Byte(byte));
+        }
+
+        let decoded = Recording::<Byte>::from_bytes(&recording.to_bytes()).unwrap();
+        assert_eq!(decoded, recording);
+    }
+
+    #[test]
+    fn rejects_other_format_versions() {
+        let mut bytes = Recording::<Byte>::new().to_bytes();
+        for version in [0, FORMAT_VERSION + 1] {
+            bytes[4] = version;
+            assert_eq!(
+                Recording::<Byte>::from_bytes(&bytes),
+                Err(ReplayError::UnsupportedVersion(version))
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_a_count_the_body_cannot_hold() {
+        let mut bytes = Recording::<Byte>::new().to_bytes();
+        bytes[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        bytes.extend_from_slice(&[1, 0, 7]);
+
+        assert_eq!(
+            Recording::<Byte>::from_bytes(&bytes),
+            Err(ReplayError::Truncated)
+        );
+    }
+
+    #[test]
+    fn rejects_a_body_cut_short() {
+        let mut recording = Recording::new();
+        recording.push(Byte(1));
+        recording.push(Byte(2));
+        let bytes = recording.to_bytes();
+
+        assert_eq!(
+            Recording::<Byte>::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ReplayError::Truncated)
+        );
+    }
+
+    /// Counts up from 0, standing in for the keyboard.
+    #[derive(Default)]
+    struct Counter(u8);
+
+    impl InputDevice for Counter {
+        type Frame = Byte;
+
+        fn connected(&self) -> bool {
+            true
+        }
+
+        fn is_fallback(&self) -> bool {
+            true
+        }
+
+        fn player_count(&self) -> u8 {
+            1
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities::default()
+        }
+
+        fn update(&mut self) -> Byte {
+            self.0 += 1;
+            Byte(self.0 - 1)
+        }
+    }
+
+    #[test]
+    fn recorder_describes_and_records_the_wrapped_device() {
+        let mut recorder = Recorder::new(Counter::default());
+        assert!(recorder.is_fallback());
+
+        for _ in 0..3 {
+            recorder.update();
+        }
+        let (_, recording) = recorder.finish();
+        assert_eq!(recording.frames(), [Byte(0), Byte(1), Byte(2)]);
+
+        let mut playback = Playback::new(recording);
+        assert!(!playback.is_fallback());
+        assert_eq!(playback.update(), Byte(0));
+    }
+}
@@ -2,7 +2,7 @@ pub mod guard;
 
 use js_sys::SharedArrayBuffer;
 use wasm_bindgen::prelude::*;
-use web_sys::{Worker, WorkerOptions, WorkerType};
+use web_sys::{MessageEvent, Worker, WorkerOptions, WorkerType};
 
 use crate::channel::PluginChannel;
 use crate::shmem_runner::guard::MemoryGuard;
@@ -24,10 +24,12 @@ pub struct PluginSharedMemoryRunner {
     memory: SharedArrayBuffer,
     worker: Worker,
     lock_view: js_sys::Int32Array,
+    message_handler: Option<Closure<dyn FnMut(MessageEvent)>>,
 }
 
 impl Drop for PluginSharedMemoryRunner {
     fn drop(&mut self) {
+        self.worker.set_onmessage(None);
         self.worker.terminate();
     }
 }
@@ -62,7 +64,7 @@ impl PluginSharedMemoryRunner {
         js_sys::Reflect::set(&init_msg, &"memory".into(), &memory)?;
 
         let transfer = js_sys::Array::new();
-        transfer.push(&channel.get_port());
+        transfer.push(channel.get_port());
 
         worker.post_message_with_transfer(&init_msg, &transfer)?;
 
@@ -73,9 +75,28 @@ impl PluginSharedMemoryRunner {
             memory,
             worker,
             lock_view,
+            message_handler: None,
         })
     }
 
+    /// Sets the handler for messages the worker sends with `post(data)`, replacing any previous one
+    pub fn set_message_handler(&mut self, mut handler: impl FnMut(JsValue) + 'static) {
+        let closure = Closure::wrap(Box::new(move |event: MessageEvent| handler(event.data()))
+            as Box<dyn FnMut(MessageEvent)>);
+
+        self.worker
+            .set_onmessage(Some(closure.as_ref().unchecked_ref()));
+        self.message_handler = Some(closure);
+    }
+
+    /// Gets an `Int32Array` over the data region for lock-free access with `Atomics`
+    ///
+    /// Only whole 32-bit words of the data region are covered.
+    pub fn atomic_view(&self) -> js_sys::Int32Array {
+        let words = (self.memory.byte_length() - DATA_OFFSET as u32) / 4;
+        js_sys::Int32Array::new_with_byte_offset_and_length(&self.memory, DATA_OFFSET as u32, words)
+    }
+
     /// Acquires the lock for Rust access (blocking)
     pub fn lock_blocking<'a>(&'a self) -> MemoryGuard<'a> {
         loop {
@@ -125,7 +146,7 @@ impl PluginSharedMemoryRunner {
 
     /// Creates the wrapper code that sets up the worker environment
     fn create_worker_code(user_code: &str) -> String {
-        static WORKER_FOOTER: &'static str = include_str!("./worker.js");
+        static WORKER_FOOTER: &str = include_str!("./worker.js");
 
         format!("{user_code}\n{WORKER_FOOTER}")
     }
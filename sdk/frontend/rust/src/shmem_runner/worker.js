@@ -78,6 +78,11 @@ function send(data) {
     }
 }
 
+// Send a message to the thread that spawned this worker
+function post(data) {
+    self.postMessage(data);
+}
+
 const pendingRequests = new Map();
 
 function generateNonce() {
@@ -130,4 +135,9 @@ function getMemory() {
 
 function getMemoryView() {
     return new Uint8Array(memory);
+}
+
+// Int32 view of the data region for lock-free access with Atomics (whole words only)
+function getAtomicView() {
+    return new Int32Array(memory, DATA_OFFSET, (memory.byteLength - DATA_OFFSET) >> 2);
 }
\ No newline at end of file